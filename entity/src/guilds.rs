@@ -1,6 +1,8 @@
+use super::sea_orm_active_enums::{ImageFormat, ProposalFormat};
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "guilds")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -8,6 +10,11 @@ pub struct Model {
     #[sea_orm(unique)]
     pub guild_id: i64,
     pub verified: bool,
+    pub mix_host_role_id: Option<i64>,
+    pub theme: Option<String>,
+    pub proposal_format: ProposalFormat,
+    pub image_format: ImageFormat,
+    pub image_quality: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -2,5 +2,9 @@ pub mod prelude;
 
 pub mod guilds;
 pub mod lobbies;
+pub mod match_channels;
+pub mod matches;
+pub mod mixes;
 pub mod players;
+pub mod queue_entries;
 pub mod sea_orm_active_enums;
@@ -1,6 +1,8 @@
+use super::sea_orm_active_enums::LobbyStatus;
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "lobbies")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -9,6 +11,20 @@ pub struct Model {
     pub main_voice_id: i64,
     pub red_team_voice_id: i64,
     pub blue_team_voice_id: i64,
+    pub name: String,
+    pub tank_slots: i32,
+    pub dps_slots: i32,
+    pub support_slots: i32,
+    pub red_team_name: String,
+    pub blue_team_name: String,
+    pub category_id: Option<i64>,
+    pub text_channel_id: Option<i64>,
+    pub return_to_main: bool,
+    pub return_delay: i32,
+    pub auto_start: bool,
+    pub use_queue: bool,
+    pub status: LobbyStatus,
+    pub show_rd: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,18 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "match_channels")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub lobby_id: i32,
+    pub position: i32,
+    pub red_team_voice_id: i64,
+    pub blue_team_voice_id: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
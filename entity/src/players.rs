@@ -1,7 +1,8 @@
 use super::sea_orm_active_enums::Role;
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "players")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -33,6 +34,14 @@ pub struct Model {
     pub primary_role: Option<Role>,
     pub secondary_role: Option<Role>,
     pub tertiary_role: Option<Role>,
+    #[serde(default)]
+    pub no_shows: i32,
+    #[serde(default)]
+    pub wins: i32,
+    #[serde(default)]
+    pub losses: i32,
+    #[serde(default)]
+    pub draws: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
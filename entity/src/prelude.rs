@@ -1,4 +1,11 @@
 pub use super::guilds::Entity as Guilds;
 pub use super::lobbies::Entity as Lobbies;
+pub use super::match_channels::Entity as MatchChannels;
+pub use super::matches::Entity as Matches;
+pub use super::mixes::Entity as Mixes;
 pub use super::players::Entity as Players;
+pub use super::queue_entries::Entity as QueueEntries;
+pub use super::sea_orm_active_enums::ImageFormat;
+pub use super::sea_orm_active_enums::LobbyStatus;
+pub use super::sea_orm_active_enums::ProposalFormat;
 pub use super::sea_orm_active_enums::Role;
@@ -1,6 +1,20 @@
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, EnumIter, DeriveActiveEnum)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Copy,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "role")]
 pub enum Role {
     #[sea_orm(string_value = "tank")]
@@ -33,3 +47,44 @@ impl From<&Role> for i32 {
         }
     }
 }
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Copy, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "lobby_status")]
+pub enum LobbyStatus {
+    #[sea_orm(string_value = "idle")]
+    Idle,
+    #[sea_orm(string_value = "proposing")]
+    Proposing,
+    #[sea_orm(string_value = "in_game")]
+    InGame,
+    #[sea_orm(string_value = "reporting")]
+    Reporting,
+}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Copy, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "proposal_format")]
+pub enum ProposalFormat {
+    #[sea_orm(string_value = "image")]
+    Image,
+    #[sea_orm(string_value = "embed")]
+    Embed,
+    #[sea_orm(string_value = "both")]
+    Both,
+}
+
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Copy, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "image_format")]
+pub enum ImageFormat {
+    #[sea_orm(string_value = "png")]
+    Png,
+    #[sea_orm(string_value = "webp")]
+    Webp,
+    #[sea_orm(string_value = "jpeg")]
+    Jpeg,
+}
@@ -4,6 +4,21 @@ mod m20230704_104934_create_guilds_table;
 mod m20230704_111535_create_lobbies_table;
 mod m20230704_112326_create_role_type;
 mod m20230704_113006_create_players_table;
+mod m20230720_101500_create_match_channels_table;
+mod m20230725_184000_create_mixes_table;
+mod m20230802_093000_add_lobby_settings;
+mod m20230806_141000_add_lobby_category;
+mod m20230809_201500_add_lobby_return_settings;
+mod m20230812_170000_add_lobby_auto_start;
+mod m20230815_110000_add_player_no_shows;
+mod m20230818_153000_create_queue_entries_table;
+mod m20230821_120000_add_lobby_status;
+mod m20230824_190000_create_matches_table;
+mod m20230827_120000_add_lobby_show_rd;
+mod m20230830_150000_add_guild_theme;
+mod m20230902_180000_add_player_results;
+mod m20230905_120000_add_guild_proposal_format;
+mod m20230908_200000_add_guild_image_format;
 
 pub struct Migrator;
 
@@ -15,6 +30,21 @@ impl MigratorTrait for Migrator {
             Box::new(m20230704_111535_create_lobbies_table::Migration),
             Box::new(m20230704_112326_create_role_type::Migration),
             Box::new(m20230704_113006_create_players_table::Migration),
+            Box::new(m20230720_101500_create_match_channels_table::Migration),
+            Box::new(m20230725_184000_create_mixes_table::Migration),
+            Box::new(m20230802_093000_add_lobby_settings::Migration),
+            Box::new(m20230806_141000_add_lobby_category::Migration),
+            Box::new(m20230809_201500_add_lobby_return_settings::Migration),
+            Box::new(m20230812_170000_add_lobby_auto_start::Migration),
+            Box::new(m20230815_110000_add_player_no_shows::Migration),
+            Box::new(m20230818_153000_create_queue_entries_table::Migration),
+            Box::new(m20230821_120000_add_lobby_status::Migration),
+            Box::new(m20230824_190000_create_matches_table::Migration),
+            Box::new(m20230827_120000_add_lobby_show_rd::Migration),
+            Box::new(m20230830_150000_add_guild_theme::Migration),
+            Box::new(m20230902_180000_add_player_results::Migration),
+            Box::new(m20230905_120000_add_guild_proposal_format::Migration),
+            Box::new(m20230908_200000_add_guild_image_format::Migration),
         ]
     }
 }
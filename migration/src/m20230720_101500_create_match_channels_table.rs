@@ -0,0 +1,96 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchChannels::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MatchChannels::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(MatchChannels::LobbyId).integer().not_null())
+                    .col(ColumnDef::new(MatchChannels::Position).integer().not_null())
+                    .col(
+                        ColumnDef::new(MatchChannels::RedTeamVoiceId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MatchChannels::BlueTeamVoiceId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .name("match_channels_pkey")
+                            .col(MatchChannels::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("match_channels_lobby_id_fkey")
+                            .from(MatchChannels::Table, MatchChannels::LobbyId)
+                            .to(Lobbies::Table, Lobbies::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("match_channels_lobby_id_position_idx")
+                    .table(MatchChannels::Table)
+                    .col(MatchChannels::LobbyId)
+                    .col(MatchChannels::Position)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("match_channels_lobby_id_position_idx")
+                    .table(MatchChannels::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(MatchChannels::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum MatchChannels {
+    Table,
+    Id,
+    LobbyId,
+    Position,
+    RedTeamVoiceId,
+    BlueTeamVoiceId,
+}
+
+#[derive(Iden)]
+enum Lobbies {
+    Table,
+    Id,
+}
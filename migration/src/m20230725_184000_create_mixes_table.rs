@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Mixes::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Mixes::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(Mixes::GuildId).big_unsigned().not_null())
+                    .col(ColumnDef::new(Mixes::LobbyId).integer().not_null())
+                    .col(ColumnDef::new(Mixes::CreatedAt).timestamp().not_null())
+                    .col(ColumnDef::new(Mixes::Request).json_binary().not_null())
+                    .col(ColumnDef::new(Mixes::Players).json_binary().not_null())
+                    .primary_key(Index::create().name("mixes_pkey").col(Mixes::Id))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("mixes_guild_id_idx")
+                    .table(Mixes::Table)
+                    .col(Mixes::GuildId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("mixes_guild_id_idx")
+                    .table(Mixes::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Mixes::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Mixes {
+    Table,
+    Id,
+    GuildId,
+    LobbyId,
+    CreatedAt,
+    Request,
+    Players,
+}
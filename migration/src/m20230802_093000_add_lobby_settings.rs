@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Lobbies::Table)
+                    .add_column(
+                        ColumnDef::new(Lobbies::Name)
+                            .string()
+                            .not_null()
+                            .default("Mix Lobby"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Lobbies::TankSlots)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .add_column(
+                        ColumnDef::new(Lobbies::DpsSlots)
+                            .integer()
+                            .not_null()
+                            .default(2),
+                    )
+                    .add_column(
+                        ColumnDef::new(Lobbies::SupportSlots)
+                            .integer()
+                            .not_null()
+                            .default(2),
+                    )
+                    .add_column(
+                        ColumnDef::new(Lobbies::RedTeamName)
+                            .string()
+                            .not_null()
+                            .default("Red"),
+                    )
+                    .add_column(
+                        ColumnDef::new(Lobbies::BlueTeamName)
+                            .string()
+                            .not_null()
+                            .default("Blue"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Lobbies::Table)
+                    .drop_column(Lobbies::Name)
+                    .drop_column(Lobbies::TankSlots)
+                    .drop_column(Lobbies::DpsSlots)
+                    .drop_column(Lobbies::SupportSlots)
+                    .drop_column(Lobbies::RedTeamName)
+                    .drop_column(Lobbies::BlueTeamName)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Lobbies {
+    Table,
+    Name,
+    TankSlots,
+    DpsSlots,
+    SupportSlots,
+    RedTeamName,
+    BlueTeamName,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Lobbies::Table)
+                    .add_column(ColumnDef::new(Lobbies::CategoryId).big_unsigned().null())
+                    .add_column(ColumnDef::new(Lobbies::TextChannelId).big_unsigned().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guilds::Table)
+                    .add_column(ColumnDef::new(Guilds::MixHostRoleId).big_unsigned().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guilds::Table)
+                    .drop_column(Guilds::MixHostRoleId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Lobbies::Table)
+                    .drop_column(Lobbies::CategoryId)
+                    .drop_column(Lobbies::TextChannelId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Lobbies {
+    Table,
+    CategoryId,
+    TextChannelId,
+}
+
+#[derive(Iden)]
+enum Guilds {
+    Table,
+    MixHostRoleId,
+}
@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(QueueEntries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(QueueEntries::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(QueueEntries::LobbyId).integer().not_null())
+                    .col(
+                        ColumnDef::new(QueueEntries::DiscordId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(QueueEntries::JoinedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .name("queue_entries_pkey")
+                            .col(QueueEntries::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("queue_entries_lobby_id_fkey")
+                            .from(QueueEntries::Table, QueueEntries::LobbyId)
+                            .to(Lobbies::Table, Lobbies::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("queue_entries_lobby_id_discord_id_idx")
+                    .table(QueueEntries::Table)
+                    .col(QueueEntries::LobbyId)
+                    .col(QueueEntries::DiscordId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Lobbies::Table)
+                    .add_column(
+                        ColumnDef::new(Lobbies::UseQueue)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Lobbies::Table)
+                    .drop_column(Lobbies::UseQueue)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("queue_entries_lobby_id_discord_id_idx")
+                    .table(QueueEntries::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(QueueEntries::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum QueueEntries {
+    Table,
+    Id,
+    LobbyId,
+    DiscordId,
+    JoinedAt,
+}
+
+#[derive(Iden)]
+enum Lobbies {
+    Table,
+    Id,
+    UseQueue,
+}
@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Matches::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Matches::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment(),
+                    )
+                    .col(ColumnDef::new(Matches::GuildId).big_unsigned().not_null())
+                    .col(ColumnDef::new(Matches::LobbyId).integer().not_null())
+                    .col(ColumnDef::new(Matches::StartedAt).timestamp().not_null())
+                    .col(ColumnDef::new(Matches::FinishedAt).timestamp())
+                    .col(ColumnDef::new(Matches::Score).float())
+                    .col(ColumnDef::new(Matches::Team1).json_binary().not_null())
+                    .col(ColumnDef::new(Matches::Team2).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(Matches::Substitutions)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .primary_key(Index::create().name("matches_pkey").col(Matches::Id))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("matches_guild_id_idx")
+                    .table(Matches::Table)
+                    .col(Matches::GuildId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("matches_guild_id_idx")
+                    .table(Matches::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Matches::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Matches {
+    Table,
+    Id,
+    GuildId,
+    LobbyId,
+    StartedAt,
+    FinishedAt,
+    Score,
+    Team1,
+    Team2,
+    Substitutions,
+}
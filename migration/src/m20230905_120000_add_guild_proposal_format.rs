@@ -0,0 +1,71 @@
+use sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ProposalFormat::Table)
+                    .values(ProposalFormat::iter().skip(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guilds::Table)
+                    .add_column(
+                        ColumnDef::new(Guilds::ProposalFormat)
+                            .custom(ProposalFormat::Table)
+                            .not_null()
+                            .default(SimpleExpr::Custom("'image'".to_string())),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guilds::Table)
+                    .drop_column(Guilds::ProposalFormat)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(
+                Type::drop()
+                    .if_exists()
+                    .name(ProposalFormat::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Guilds {
+    Table,
+    ProposalFormat,
+}
+
+#[derive(Iden, EnumIter)]
+enum ProposalFormat {
+    Table,
+    Image,
+    Embed,
+    Both,
+}
@@ -0,0 +1,74 @@
+use sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ImageFormat::Table)
+                    .values(ImageFormat::iter().skip(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guilds::Table)
+                    .add_column(
+                        ColumnDef::new(Guilds::ImageFormat)
+                            .custom(ImageFormat::Table)
+                            .not_null()
+                            .default(SimpleExpr::Custom("'png'".to_string())),
+                    )
+                    .add_column(
+                        ColumnDef::new(Guilds::ImageQuality)
+                            .integer()
+                            .not_null()
+                            .default(90),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Guilds::Table)
+                    .drop_column(Guilds::ImageFormat)
+                    .drop_column(Guilds::ImageQuality)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().if_exists().name(ImageFormat::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Guilds {
+    Table,
+    ImageFormat,
+    ImageQuality,
+}
+
+#[derive(Iden, EnumIter)]
+enum ImageFormat {
+    Table,
+    Png,
+    Webp,
+    Jpeg,
+}
@@ -0,0 +1,218 @@
+use itertools::Itertools;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::futures::future::join_all;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::{
+    application_command::ApplicationCommandInteraction, InteractionResponseType,
+};
+use serenity::model::id::UserId;
+use std::collections::HashSet;
+use tracing::log::warn;
+
+use crate::bot::commands::MixerCommand;
+use crate::database::queries::prelude::*;
+use crate::database::DatabaseContainer;
+use crate::image_manipulation::avatar::AvatarCacheContainer;
+use crate::image_manipulation::encoding::OutputFormat;
+use crate::image_manipulation::{ImageGeneratorContainer, LeaderboardColumn, LeaderboardEntry};
+use crate::mixer::player::Player;
+use entity::prelude::Role;
+
+const DEFAULT_COUNT: i64 = 10;
+const MAX_COUNT: i64 = 20;
+const PAGE_SIZE: u64 = 100;
+
+#[derive(Clone)]
+pub struct LeaderboardCommand;
+
+#[async_trait]
+impl MixerCommand for LeaderboardCommand {
+    fn name(&self) -> String {
+        "leaderboard".to_string()
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name(self.name())
+            .description("Show the best players of the server on each role")
+            .create_option(|option| {
+                option
+                    .name("count")
+                    .description("How many players to show per role")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .max_int_value(MAX_COUNT)
+            })
+            .dm_permission(false);
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let guild_id = interaction.guild_id.unwrap();
+        let count = interaction
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "count")
+            .and_then(|option| option.value.as_ref())
+            .and_then(|value| value.as_i64())
+            .unwrap_or(DEFAULT_COUNT)
+            .clamp(1, MAX_COUNT);
+
+        interaction
+            .create_interaction_response(ctx, |response| {
+                response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            })
+            .await?;
+
+        let members = ctx
+            .cache
+            .guild(guild_id)
+            .map(|guild| guild.members.keys().copied().collect::<HashSet<_>>())
+            .unwrap_or_default();
+
+        let (guild, players) = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            let guild = GuildQuery::guild_by_guild_id(db.connection(), guild_id).await;
+
+            // players aren't stored per guild, so go through the global ranking until
+            // enough members of this guild are found
+            let mut players = Vec::new();
+            for role in [Role::Tank, Role::Dps, Role::Support] {
+                let mut top = Vec::new();
+                for page in 0.. {
+                    let Some(ranking) =
+                        PlayerQuery::top_by_role(db.connection(), role, page, PAGE_SIZE).await
+                    else {
+                        break;
+                    };
+                    let last_page = (ranking.len() as u64) < PAGE_SIZE;
+
+                    top.extend(
+                        ranking
+                            .into_iter()
+                            .filter(|player| members.contains(&UserId(player.discord_id as u64)))
+                            .map(Player::new),
+                    );
+
+                    if top.len() >= count as usize || last_page {
+                        break;
+                    }
+                }
+                top.truncate(count as usize);
+                players.push((role, top));
+            }
+
+            (guild, players)
+        };
+        let theme = guild.as_ref().and_then(|guild| guild.theme.clone());
+        let format = guild.as_ref().map(OutputFormat::from).unwrap_or_default();
+
+        if players.iter().all(|(_, players)| players.is_empty()) {
+            interaction
+                .edit_original_interaction_response(ctx, |response| {
+                    response.content("No one has played in this server yet")
+                })
+                .await?;
+            return Ok(());
+        }
+
+        let avatars = ctx
+            .data
+            .read()
+            .await
+            .get::<AvatarCacheContainer>()
+            .unwrap()
+            .clone();
+
+        let mut columns = Vec::new();
+        for (role, players) in &players {
+            let role = *role;
+            let entries = join_all(players.iter().map(|player| {
+                let avatars = avatars.clone();
+                async move {
+                    let (name, avatar) = match player.discord_id.to_user(ctx).await {
+                        Ok(user) => {
+                            let avatar = avatars.avatar(&user).await;
+                            (user.name, avatar)
+                        }
+                        Err(_) => (
+                            player.bn_name.clone().unwrap_or("Unknown".to_string()),
+                            None,
+                        ),
+                    };
+
+                    LeaderboardEntry {
+                        name,
+                        avatar,
+                        rating: player.ranks[&role].value as i32,
+                    }
+                }
+            }))
+            .await;
+
+            columns.push(LeaderboardColumn { role, entries });
+        }
+
+        let image_gen = ctx
+            .data
+            .read()
+            .await
+            .get::<ImageGeneratorContainer>()
+            .unwrap()
+            .clone();
+        let result = image_gen
+            .render(move |image_gen| {
+                image_gen.draw_leaderboard_to_vec(theme.as_deref(), &columns, format)
+            })
+            .await;
+
+        interaction
+            .create_followup_message(ctx, |message| match result {
+                Ok(image) => {
+                    if let Some(notice) = &image.notice {
+                        message.content(notice);
+                    }
+                    message.add_file(image.into_attachment("leaderboard"))
+                }
+                Err(why) => {
+                    warn!("Could not render the leaderboard: {}", why);
+                    message
+                        .content(leaderboard_text(&players))
+                        .allowed_mentions(|mentions| mentions.empty_users())
+                }
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn leaderboard_text(players: &[(Role, Vec<Player>)]) -> String {
+    players
+        .iter()
+        .map(|(role, players)| {
+            let entries = players
+                .iter()
+                .enumerate()
+                .map(|(i, player)| {
+                    format!(
+                        "{}. <@{}> {}",
+                        i + 1,
+                        player.discord_id.0,
+                        player.ranks[role].value as i32
+                    )
+                })
+                .join("\n");
+
+            format!("**{}**\n{}", role, entries)
+        })
+        .join("\n\n")
+}
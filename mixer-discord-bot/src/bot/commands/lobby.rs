@@ -1,35 +1,71 @@
+use image::RgbaImage;
 use itertools::Itertools;
+use sea_orm::prelude::DateTime;
+use sea_orm::Iterable;
 use serenity::async_trait;
-use serenity::builder::CreateApplicationCommand;
+use serenity::builder::{CreateApplicationCommand, CreateComponents, CreateEmbed};
 use serenity::client::Context;
 use serenity::futures::future::join_all;
 use serenity::futures::StreamExt;
 use serenity::model::application::command::CommandOptionType;
 use serenity::model::application::component::ButtonStyle;
 use serenity::model::application::interaction::{
-    application_command::ApplicationCommandInteraction, InteractionResponseType,
+    application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+    message_component::MessageComponentInteraction,
+    InteractionResponseType,
 };
-use serenity::model::channel::{ChannelType, PermissionOverwrite, PermissionOverwriteType};
-use serenity::model::id::{ChannelId, RoleId, UserId};
-use serenity::model::prelude::{AttachmentType, GuildId, Message};
+use serenity::model::channel::ChannelType;
+use serenity::model::id::{ChannelId, UserId};
+use serenity::model::prelude::{GuildId, Message};
 use serenity::model::Permissions;
 use sqlx::types::chrono::Utc;
-use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Duration;
+use tracing::log::warn;
 
 use crate::bot::commands::MixerCommand;
+use crate::bot::handlers::lobby_handler::{LobbyHandler, MatchTicket, MatchTracker, Session};
 use crate::database::queries::prelude::*;
 use crate::database::DatabaseContainer;
-use crate::image_manipulation::ImageGeneratorContainer;
+use crate::image_manipulation::avatar::{AvatarCache, AvatarCacheContainer};
+use crate::image_manipulation::encoding::{EncodedImage, OutputFormat};
+use crate::image_manipulation::{
+    ImageGenerator, ImageGeneratorContainer, RenderError, ResultImage, ResultPlayerImage,
+    ResultTeamImage, SlotImage, TeamImage,
+};
+use crate::mixer::draft::Draft;
 use crate::mixer::mixer;
+use crate::mixer::mixer::{MixMode, MixRequest};
+use crate::mixer::participant::{Participant, Substitution};
 use crate::mixer::player::Player;
+use crate::mixer::rating::Rating;
 use crate::mixer::team::Team;
 use entity::lobbies;
-use entity::prelude::Role;
+use entity::prelude::{LobbyStatus, ProposalFormat, Role};
 
 #[derive(Clone)]
 pub struct LobbyCommand;
 
+const READY_CHECK_TIMEOUT: u64 = 60;
+const MOVE_ATTEMPTS: usize = 2;
+const MOVE_RETRY_DELAY: u64 = 2;
+const MIN_PLAYED_SHARE: f32 = 0.25;
+// discord allows 25 options per select menu and five rows, one is kept for the back button
+const MAX_SELECT_OPTIONS: usize = 25;
+const MAX_SELECT_MENUS: usize = 4;
+// the column defaults of a new lobby, used to set up its channels before it is stored
+const DEFAULT_SLOTS: [i32; 3] = [1, 2, 2];
+const DEFAULT_TEAM_NAMES: [&str; 2] = ["Red", "Blue"];
+
+struct MixHost<'a> {
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    author: Option<UserId>,
+    interaction: Option<&'a ApplicationCommandInteraction>,
+    session: &'a Session,
+}
+
 #[async_trait]
 impl MixerCommand for LobbyCommand {
     fn name(&self) -> String {
@@ -39,18 +75,291 @@ impl MixerCommand for LobbyCommand {
     fn create(&self, command: &mut CreateApplicationCommand) {
         command
             .name(self.name())
-            .description("Create or start a lobby")
+            .description("Create, manage or start a lobby")
             .create_option(|option| {
                 option
                     .name("create")
                     .description("Create a lobby")
                     .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("name")
+                            .description("Name of the lobby")
+                            .kind(CommandOptionType::String)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("category")
+                            .description("Existing category to create the lobby in")
+                            .kind(CommandOptionType::Channel)
+                            .channel_types(&[ChannelType::Category])
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("tank")
+                            .description("Tank slots per team")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .max_int_value(5)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("dps")
+                            .description("Damage slots per team")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .max_int_value(5)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("support")
+                            .description("Support slots per team")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .max_int_value(5)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("red_name")
+                            .description("Name of the red team channels")
+                            .kind(CommandOptionType::String)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("blue_name")
+                            .description("Name of the blue team channels")
+                            .kind(CommandOptionType::String)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("adopt")
+                    .description("Register existing voice channels as a lobby")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("main")
+                            .description("The channel players wait in")
+                            .kind(CommandOptionType::Channel)
+                            .channel_types(&[ChannelType::Voice])
+                            .required(true)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("red")
+                            .description("The red team channel")
+                            .kind(CommandOptionType::Channel)
+                            .channel_types(&[ChannelType::Voice])
+                            .required(true)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("blue")
+                            .description("The blue team channel")
+                            .kind(CommandOptionType::Channel)
+                            .channel_types(&[ChannelType::Voice])
+                            .required(true)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("text")
+                            .description("The channel for team proposals and results")
+                            .kind(CommandOptionType::Channel)
+                            .channel_types(&[ChannelType::Text])
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("name")
+                            .description("Name of the lobby")
+                            .kind(CommandOptionType::String)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("list")
+                    .description("List the lobbies of this server")
+                    .kind(CommandOptionType::SubCommand)
+            })
+            .create_option(|option| {
+                option
+                    .name("status")
+                    .description("Show what the lobbies of this server are doing")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("lobby")
+                            .description("The number of the lobby")
+                            .kind(CommandOptionType::Integer)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("config")
+                    .description("Change the settings of a lobby")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("lobby")
+                            .description("The number of the lobby")
+                            .kind(CommandOptionType::Integer)
+                            .required(true)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("name")
+                            .description("Name of the lobby")
+                            .kind(CommandOptionType::String)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("tank")
+                            .description("Tank slots per team")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .max_int_value(5)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("dps")
+                            .description("Damage slots per team")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .max_int_value(5)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("support")
+                            .description("Support slots per team")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .max_int_value(5)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("red_name")
+                            .description("Name of the red team channels")
+                            .kind(CommandOptionType::String)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("blue_name")
+                            .description("Name of the blue team channels")
+                            .kind(CommandOptionType::String)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("category")
+                            .description("Move the lobby channels into this category")
+                            .kind(CommandOptionType::Channel)
+                            .channel_types(&[ChannelType::Category])
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("queue")
+                            .description(
+                                "Mix players from the text queue instead of the voice channel",
+                            )
+                            .kind(CommandOptionType::Boolean)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("auto_start")
+                            .description("Start a ready check once the lobby is full")
+                            .kind(CommandOptionType::Boolean)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("show_rd")
+                            .description("Show the rating deviation of players on the teams image")
+                            .kind(CommandOptionType::Boolean)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("return")
+                            .description("Move the players back to the lobby after a match")
+                            .kind(CommandOptionType::Boolean)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("return_delay")
+                            .description("Seconds to wait before moving the players back")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .max_int_value(600)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("delete")
+                    .description("Delete a lobby")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("lobby")
+                            .description("The number of the lobby")
+                            .kind(CommandOptionType::Integer)
+                            .required(true)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("channels")
+                            .description("Also delete the voice channels of the lobby")
+                            .kind(CommandOptionType::Boolean)
+                    })
             })
             .create_option(|option| {
                 option
                     .name("start")
                     .description("Start a lobby")
                     .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("mode")
+                            .description("How players are split into teams")
+                            .kind(CommandOptionType::String)
+                            .add_string_choice("Role queue", "role")
+                            .add_string_choice("Open queue", "open")
+                            .add_string_choice("Captain draft", "draft")
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("teams")
+                            .description("Number of teams to split the lobby into")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(2)
+                            .max_int_value(8)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("lobby")
+                            .description("The number of the lobby, if you are not in its channel")
+                            .kind(CommandOptionType::Integer)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("captain1")
+                            .description("First captain for the draft mode")
+                            .kind(CommandOptionType::User)
+                    })
+                    .create_sub_option(|option| {
+                        option
+                            .name("captain2")
+                            .description("Second captain for the draft mode")
+                            .kind(CommandOptionType::User)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("replay")
+                    .description("Reproduce the teams of a previous mix")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("mix")
+                            .description("The number of the mix")
+                            .kind(CommandOptionType::Integer)
+                            .required(true)
+                    })
             })
             .default_member_permissions(Permissions::MOVE_MEMBERS)
             .dm_permission(false);
@@ -63,7 +372,13 @@ impl MixerCommand for LobbyCommand {
     ) -> serenity::Result<()> {
         match interaction.data.options.get(0).unwrap().name.as_str() {
             "create" => self.create_lobby(ctx, interaction).await,
+            "adopt" => self.adopt_lobby(ctx, interaction).await,
+            "list" => self.list_lobbies(ctx, interaction).await,
+            "status" => self.lobby_status(ctx, interaction).await,
+            "config" => self.config_lobby(ctx, interaction).await,
+            "delete" => self.delete_lobby(ctx, interaction).await,
             "start" => self.start_lobby(ctx, interaction).await,
+            "replay" => self.replay_mix(ctx, interaction).await,
             _ => Ok(()),
         }
     }
@@ -75,21 +390,7 @@ impl LobbyCommand {
         ctx: &Context,
         interaction: ApplicationCommandInteraction,
     ) -> serenity::Result<()> {
-        let guild_id = interaction.guild_id.unwrap();
-        let guild = guild_id.to_partial_guild(ctx).await?;
-        let member = guild.member(ctx, interaction.user.id).await?;
-
-        let mut has_permission = false;
-        if guild.owner_id == member.user.id {
-            has_permission = true;
-        }
-        if let Ok(perms) = member.permissions(ctx) {
-            if perms.administrator() {
-                has_permission = true;
-            }
-        }
-
-        if !has_permission {
+        if !self.has_permission(ctx, &interaction).await? {
             interaction
                 .create_interaction_response(ctx, |response| {
                     response
@@ -102,135 +403,352 @@ impl LobbyCommand {
             return Ok(());
         }
 
-        let data = ctx.data.read().await;
-        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+        let integer = |name| match self.resolved_option(&interaction, name) {
+            Some(CommandDataOptionValue::Integer(value)) => Some(value as i32),
+            _ => None,
+        };
+        let tank_slots = integer("tank");
+        let dps_slots = integer("dps");
+        let support_slots = integer("support");
+        let red_team_name = self.string_option(&interaction, "red_name");
+        let blue_team_name = self.string_option(&interaction, "blue_name");
 
-        let main_voice = interaction
-            .guild_id
-            .unwrap()
-            .create_channel(ctx, |c| c.name("Mix Lobby").kind(ChannelType::Voice))
+        let [default_tank, default_dps, default_support] = DEFAULT_SLOTS;
+        let team_size = tank_slots.unwrap_or(default_tank)
+            + dps_slots.unwrap_or(default_dps)
+            + support_slots.unwrap_or(default_support);
+        if team_size == 0 {
+            return self
+                .respond(ctx, &interaction, "A team needs at least one slot!")
+                .await;
+        }
+
+        interaction
+            .create_interaction_response(ctx, |response| {
+                response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            })
             .await?;
 
-        let permissions = vec![PermissionOverwrite {
-            allow: Permissions::empty(),
-            deny: Permissions::VIEW_CHANNEL,
-            kind: PermissionOverwriteType::Role(RoleId::from(interaction.guild_id.unwrap().0)),
-        }];
-        let red_voice = interaction
-            .guild_id
-            .unwrap()
+        let guild_id = interaction.guild_id.unwrap();
+        let name = self
+            .string_option(&interaction, "name")
+            .unwrap_or_else(|| "Mix Lobby".to_string());
+        let host_role = LobbyHandler::host_role(ctx, guild_id).await;
+        let permissions = |deny| {
+            LobbyHandler::permissions(guild_id, ctx.cache.current_user_id(), host_role, deny)
+        };
+
+        let category = match self.resolved_option(&interaction, "category") {
+            Some(CommandDataOptionValue::Channel(category)) => category.id,
+            _ => {
+                guild_id
+                    .create_channel(ctx, |c| {
+                        c.name(&name)
+                            .kind(ChannelType::Category)
+                            .permissions(permissions(Permissions::empty()))
+                    })
+                    .await?
+                    .id
+            }
+        };
+
+        let text_channel = guild_id
+            .create_channel(ctx, |c| {
+                c.name(&name)
+                    .kind(ChannelType::Text)
+                    .category(category)
+                    .permissions(permissions(Permissions::SEND_MESSAGES))
+            })
+            .await?;
+        let main_voice = guild_id
             .create_channel(ctx, |c| {
-                c.name("Red")
+                c.name(&name)
                     .kind(ChannelType::Voice)
-                    .user_limit(5)
-                    .permissions(permissions.clone())
+                    .category(category)
+                    .permissions(permissions(Permissions::empty()))
             })
             .await?;
-        let blue_voice = interaction
-            .guild_id
-            .unwrap()
+        let [default_red_name, default_blue_name] = DEFAULT_TEAM_NAMES;
+        let red_voice = guild_id
+            .create_channel(ctx, |c| {
+                c.name(red_team_name.as_deref().unwrap_or(default_red_name))
+                    .kind(ChannelType::Voice)
+                    .category(category)
+                    .user_limit(team_size as u32)
+                    .permissions(permissions(Permissions::CONNECT))
+            })
+            .await?;
+        let blue_voice = guild_id
             .create_channel(ctx, |c| {
-                c.name("Blue")
+                c.name(blue_team_name.as_deref().unwrap_or(default_blue_name))
                     .kind(ChannelType::Voice)
-                    .user_limit(5)
-                    .permissions(permissions)
+                    .category(category)
+                    .user_limit(team_size as u32)
+                    .permissions(permissions(Permissions::CONNECT))
             })
             .await?;
 
-        LobbyQuery::create(
-            db.connection(),
-            interaction.guild_id.unwrap(),
-            main_voice.id,
-            red_voice.id,
-            blue_voice.id,
-        )
-        .await;
+        let lobby = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
 
-        drop(db);
-        drop(data);
+            let lobby = LobbyQuery::create(
+                db.connection(),
+                guild_id,
+                name,
+                Some(category),
+                Some(text_channel.id),
+                main_voice.id,
+                red_voice.id,
+                blue_voice.id,
+            )
+            .await;
+
+            match lobby {
+                Some(mut lobby)
+                    if tank_slots.is_some()
+                        || dps_slots.is_some()
+                        || support_slots.is_some()
+                        || red_team_name.is_some()
+                        || blue_team_name.is_some() =>
+                {
+                    lobby.tank_slots = tank_slots.unwrap_or(lobby.tank_slots);
+                    lobby.dps_slots = dps_slots.unwrap_or(lobby.dps_slots);
+                    lobby.support_slots = support_slots.unwrap_or(lobby.support_slots);
+                    if let Some(red_team_name) = red_team_name {
+                        lobby.red_team_name = red_team_name;
+                    }
+                    if let Some(blue_team_name) = blue_team_name {
+                        lobby.blue_team_name = blue_team_name;
+                    }
+                    LobbyQuery::update(db.connection(), lobby).await
+                }
+                lobby => lobby,
+            }
+        };
 
         interaction
-            .create_interaction_response(ctx, |response| {
-                response
-                    .kind(InteractionResponseType::ChannelMessageWithSource)
-                    .interaction_response_data(|message| {
-                        message.content("Successfully created a new mix lobby!")
-                    })
+            .edit_original_interaction_response(ctx, |response| match lobby {
+                Some(lobby) => response.content(format!(
+                    "Successfully created a new mix lobby #{} in <#{}>!",
+                    lobby.id, text_channel.id
+                )),
+                None => response.content("Failed to create the lobby!"),
             })
             .await?;
 
         Ok(())
     }
 
-    async fn start_lobby(
+    async fn adopt_lobby(
         &self,
         ctx: &Context,
         interaction: ApplicationCommandInteraction,
     ) -> serenity::Result<()> {
+        if !self.has_permission(ctx, &interaction).await? {
+            return self
+                .respond(
+                    ctx,
+                    &interaction,
+                    "You don't have permission to adopt a lobby!",
+                )
+                .await;
+        }
+
+        let channels = ["main", "red", "blue"]
+            .into_iter()
+            .filter_map(|name| match self.resolved_option(&interaction, name) {
+                Some(CommandDataOptionValue::Channel(channel)) => Some(channel),
+                _ => None,
+            })
+            .collect_vec();
+
+        if channels.len() != 3 || !channels.iter().map(|c| c.id).all_unique() {
+            return self
+                .respond(
+                    ctx,
+                    &interaction,
+                    "Please choose three different voice channels!",
+                )
+                .await;
+        }
+
+        let guild_id = interaction.guild_id.unwrap();
+        let name = self
+            .string_option(&interaction, "name")
+            .or_else(|| channels[0].name.clone())
+            .unwrap_or_else(|| "Mix Lobby".to_string());
+
         let data = ctx.data.read().await;
         let db = data.get::<DatabaseContainer>().unwrap().read().await;
 
-        let guild_id = interaction.guild_id.unwrap();
-        let member = guild_id.member(ctx, interaction.user.id).await?;
+        for channel in &channels {
+            if LobbyQuery::lobby_by_channel_id(db.connection(), guild_id, channel.id)
+                .await
+                .is_some()
+            {
+                return self
+                    .respond(
+                        ctx,
+                        &interaction,
+                        format!("<#{}> already belongs to a lobby!", channel.id),
+                    )
+                    .await;
+            }
+        }
 
-        let channels = guild_id.channels(ctx).await?;
+        let text_channel = match self.resolved_option(&interaction, "text") {
+            Some(CommandDataOptionValue::Channel(channel)) => Some(channel.id),
+            _ => None,
+        };
 
-        let mut is_in_lobby = false;
-        let mut channel_id = None;
-        for (id, channel) in channels {
-            if channel.kind != ChannelType::Voice {
-                continue;
+        let lobby = LobbyQuery::create(
+            db.connection(),
+            guild_id,
+            name,
+            None,
+            text_channel,
+            channels[0].id,
+            channels[1].id,
+            channels[2].id,
+        )
+        .await;
+
+        drop(db);
+        drop(data);
+
+        match lobby {
+            Some(lobby) => {
+                self.respond(
+                    ctx,
+                    &interaction,
+                    format!("Successfully adopted the channels as lobby #{}!", lobby.id),
+                )
+                .await
             }
-            let members = channel.members(ctx).await?;
-            if members.iter().any(|m| m.user.id == member.user.id)
-                && LobbyQuery::lobby_by_channel_id(db.connection(), guild_id, id)
+            None => {
+                self.respond(ctx, &interaction, "Failed to adopt the channels!")
                     .await
-                    .is_some()
-            {
-                is_in_lobby = true;
-                channel_id = Some(id);
-                break;
             }
         }
+    }
 
-        if !is_in_lobby {
-            interaction
-                .create_interaction_response(ctx, |response| {
-                    response
-                        .kind(InteractionResponseType::ChannelMessageWithSource)
-                        .interaction_response_data(|message| {
-                            message
-                                .content("You are not in the mix lobby!")
-                                .ephemeral(true)
-                        })
+    async fn list_lobbies(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let lobbies = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            LobbyQuery::lobbies_by_guild_id(db.connection(), interaction.guild_id.unwrap())
+                .await
+                .unwrap_or_default()
+        };
+
+        let content = if lobbies.is_empty() {
+            "There are no lobbies on this server!".to_string()
+        } else {
+            lobbies
+                .iter()
+                .map(|lobby| {
+                    format!(
+                        "**#{}** {} - <#{}> ({} tank, {} dps, {} support)",
+                        lobby.id,
+                        lobby.name,
+                        lobby.main_voice_id,
+                        lobby.tank_slots,
+                        lobby.dps_slots,
+                        lobby.support_slots
+                    )
                 })
-                .await?;
+                .join("\n")
+        };
 
-            return Ok(());
+        self.respond(ctx, &interaction, content).await
+    }
+
+    async fn lobby_status(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let lobbies = match self.resolved_option(&interaction, "lobby") {
+            Some(_) => match self.lobby_option(ctx, &interaction).await {
+                Some(lobby) => vec![lobby],
+                None => return self.respond(ctx, &interaction, "Lobby not found!").await,
+            },
+            None => {
+                let data = ctx.data.read().await;
+                let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+                LobbyQuery::lobbies_by_guild_id(db.connection(), interaction.guild_id.unwrap())
+                    .await
+                    .unwrap_or_default()
+            }
+        };
+
+        let content = if lobbies.is_empty() {
+            "There are no lobbies on this server!".to_string()
+        } else {
+            lobbies
+                .iter()
+                .map(|lobby| {
+                    let status = match lobby.status {
+                        LobbyStatus::Idle => "Idle",
+                        LobbyStatus::Proposing => "Proposing teams",
+                        LobbyStatus::InGame => "In game",
+                        LobbyStatus::Reporting => "Reporting the result",
+                    };
+                    format!("**#{}** {} - {}", lobby.id, lobby.name, status)
+                })
+                .join("\n")
+        };
+
+        self.respond(ctx, &interaction, content).await
+    }
+
+    async fn config_lobby(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        if !self.has_permission(ctx, &interaction).await? {
+            return self
+                .respond(
+                    ctx,
+                    &interaction,
+                    "You don't have permission to configure a lobby!",
+                )
+                .await;
         }
 
-        let lobby = LobbyQuery::lobby_by_channel_id(db.connection(), guild_id, channel_id.unwrap())
-            .await
-            .unwrap();
+        let lobby = match self.lobby_option(ctx, &interaction).await {
+            Some(lobby) => lobby,
+            None => return self.respond(ctx, &interaction, "Lobby not found!").await,
+        };
 
-        let main_channel = ChannelId::from(lobby.main_voice_id as u64)
-            .to_channel(ctx)
-            .await
-            .unwrap()
-            .guild()
-            .unwrap();
-        let red_channel = ChannelId::from(lobby.red_team_voice_id as u64)
-            .to_channel(ctx)
-            .await
-            .unwrap()
-            .guild()
-            .unwrap();
-        let blue_channel = ChannelId::from(lobby.blue_team_voice_id as u64)
-            .to_channel(ctx)
-            .await
-            .unwrap()
-            .guild()
-            .unwrap();
+        let integer = |name| match self.resolved_option(&interaction, name) {
+            Some(CommandDataOptionValue::Integer(value)) => Some(value as i32),
+            _ => None,
+        };
+        let name = self.string_option(&interaction, "name");
+        let red_team_name = self.string_option(&interaction, "red_name");
+        let blue_team_name = self.string_option(&interaction, "blue_name");
+        let tank_slots = integer("tank").unwrap_or(lobby.tank_slots);
+        let dps_slots = integer("dps").unwrap_or(lobby.dps_slots);
+        let support_slots = integer("support").unwrap_or(lobby.support_slots);
+        let category = match self.resolved_option(&interaction, "category") {
+            Some(CommandDataOptionValue::Channel(channel)) => Some(channel.id),
+            _ => None,
+        };
+
+        if tank_slots + dps_slots + support_slots == 0 {
+            return self
+                .respond(ctx, &interaction, "A team needs at least one slot!")
+                .await;
+        }
 
         interaction
             .create_interaction_response(ctx, |response| {
@@ -238,134 +756,1735 @@ impl LobbyCommand {
             })
             .await?;
 
-        for member in red_channel.members(ctx).await? {
-            member.move_to_voice_channel(ctx, main_channel.id).await?;
+        let mut updated = lobby.clone();
+        if let Some(name) = name.clone() {
+            updated.name = name;
+        }
+        if let Some(red_team_name) = red_team_name.clone() {
+            updated.red_team_name = red_team_name;
         }
-        for member in blue_channel.members(ctx).await? {
-            member.move_to_voice_channel(ctx, main_channel.id).await?;
+        if let Some(blue_team_name) = blue_team_name.clone() {
+            updated.blue_team_name = blue_team_name;
+        }
+        updated.tank_slots = tank_slots;
+        updated.dps_slots = dps_slots;
+        updated.support_slots = support_slots;
+        if let Some(category) = category {
+            updated.category_id = Some(category.0 as i64);
+        }
+        if let Some(CommandDataOptionValue::Boolean(return_to_main)) =
+            self.resolved_option(&interaction, "return")
+        {
+            updated.return_to_main = return_to_main;
+        }
+        if let Some(return_delay) = integer("return_delay") {
+            updated.return_delay = return_delay;
+        }
+        if let Some(CommandDataOptionValue::Boolean(use_queue)) =
+            self.resolved_option(&interaction, "queue")
+        {
+            updated.use_queue = use_queue;
+        }
+        if let Some(CommandDataOptionValue::Boolean(auto_start)) =
+            self.resolved_option(&interaction, "auto_start")
+        {
+            updated.auto_start = auto_start;
+        }
+        if let Some(CommandDataOptionValue::Boolean(show_rd)) =
+            self.resolved_option(&interaction, "show_rd")
+        {
+            updated.show_rd = show_rd;
         }
 
-        let members = main_channel.members(ctx).await?;
-        let users = members.iter().map(|m| m.user.id).collect::<Vec<UserId>>();
-        let players = PlayerQuery::players_by_user_ids(db.connection(), users).await;
+        let (updated, match_channels) = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
 
-        let players = match players {
-            Some(p) => p,
+            (
+                LobbyQuery::update(db.connection(), updated).await,
+                LobbyQuery::match_channels(db.connection(), lobby.id)
+                    .await
+                    .unwrap_or_default(),
+            )
+        };
+
+        let updated = match updated {
+            Some(updated) => updated,
             None => {
                 interaction
                     .edit_original_interaction_response(ctx, |response| {
-                        response.content("Failed to get players")
+                        response.content("Failed to update the lobby!")
                     })
                     .await?;
                 return Ok(());
             }
         };
 
-        let players = players
-            .into_iter()
-            .map(Player::new)
-            .collect::<Vec<Player>>();
-        let slots = vec![
-            Role::Tank,
-            Role::Dps,
-            Role::Dps,
-            Role::Support,
-            Role::Support,
-        ];
+        let team_size = (tank_slots + dps_slots + support_slots) as u64;
+        let size_changed = team_size != self.lobby_slots(&lobby).len() as u64;
 
-        interaction
-            .edit_original_interaction_response(ctx, |response| response.content("Mixing teams..."))
-            .await?;
+        // channel renames are heavily rate limited, so only touch what actually changed
+        let main_channels = [Some(lobby.main_voice_id), lobby.text_channel_id];
+        for channel_id in main_channels.into_iter().flatten() {
+            if name.is_none() && category.is_none() {
+                break;
+            }
 
-        if let Some(teams) = mixer::mix_players(&players, slots) {
-            // let interaction = interaction.clone();
-            self.process_valid_teams(ctx, interaction, lobby, teams, players)
-                .await?;
-        } else {
-            interaction
-                .edit_original_interaction_response(ctx, |response| {
-                    response.content("Fair teams' composition could not be found!")
+            ChannelId::from(channel_id as u64)
+                .edit(ctx, |c| {
+                    if let Some(name) = &name {
+                        c.name(name);
+                    }
+                    if let Some(category) = category {
+                        c.category(category);
+                    }
+                    c
                 })
                 .await?;
         }
 
-        Ok(())
-    }
+        let mut team_channels = vec![(
+            None,
+            ChannelId::from(lobby.red_team_voice_id as u64),
+            ChannelId::from(lobby.blue_team_voice_id as u64),
+        )];
+        for channels in match_channels {
+            team_channels.push((
+                Some(channels.position + 1),
+                ChannelId::from(channels.red_team_voice_id as u64),
+                ChannelId::from(channels.blue_team_voice_id as u64),
+            ));
+        }
 
-    async fn process_valid_teams(
-        &self,
-        ctx: &Context,
-        interaction: ApplicationCommandInteraction,
-        lobby: lobbies::Model,
-        teams: (Team, Team),
-        players: Vec<Player>,
+        for (position, red_channel, blue_channel) in team_channels {
+            for (channel_id, team_name) in [
+                (
+                    red_channel,
+                    red_team_name.as_ref().map(|_| &updated.red_team_name),
+                ),
+                (
+                    blue_channel,
+                    blue_team_name.as_ref().map(|_| &updated.blue_team_name),
+                ),
+            ] {
+                if team_name.is_none() && category.is_none() && !size_changed {
+                    continue;
+                }
+
+                channel_id
+                    .edit(ctx, |c| {
+                        if let Some(team_name) = team_name {
+                            match position {
+                                Some(position) => c.name(format!("{team_name} {position}")),
+                                None => c.name(team_name),
+                            };
+                        }
+                        if let Some(category) = category {
+                            c.category(category);
+                        }
+                        c.user_limit(team_size)
+                    })
+                    .await?;
+            }
+        }
+
+        interaction
+            .edit_original_interaction_response(ctx, |response| {
+                response.content(format!("Lobby #{} updated!", updated.id))
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_lobby(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        if !self.has_permission(ctx, &interaction).await? {
+            return self
+                .respond(
+                    ctx,
+                    &interaction,
+                    "You don't have permission to delete a lobby!",
+                )
+                .await;
+        }
+
+        let lobby = match self.lobby_option(ctx, &interaction).await {
+            Some(lobby) => lobby,
+            None => return self.respond(ctx, &interaction, "Lobby not found!").await,
+        };
+        let delete_channels = matches!(
+            self.resolved_option(&interaction, "channels"),
+            Some(CommandDataOptionValue::Boolean(true))
+        );
+
+        let (deleted, match_channels) = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            // match channels are removed together with the lobby
+            let match_channels = LobbyQuery::match_channels(db.connection(), lobby.id)
+                .await
+                .unwrap_or_default();
+
+            (
+                LobbyQuery::delete(db.connection(), lobby.id).await,
+                match_channels,
+            )
+        };
+
+        if !deleted {
+            return self
+                .respond(ctx, &interaction, "Failed to delete the lobby!")
+                .await;
+        }
+
+        if delete_channels {
+            let mut channels = vec![
+                lobby.main_voice_id,
+                lobby.red_team_voice_id,
+                lobby.blue_team_voice_id,
+            ];
+            channels.extend(lobby.text_channel_id);
+            for match_channels in match_channels {
+                channels.push(match_channels.red_team_voice_id);
+                channels.push(match_channels.blue_team_voice_id);
+            }
+
+            // respond first, the command might have been used in the lobby's text channel
+            self.respond(ctx, &interaction, format!("Lobby #{} deleted!", lobby.id))
+                .await?;
+
+            for channel in channels {
+                // the channel might have been removed by hand already
+                ChannelId::from(channel as u64).delete(ctx).await.ok();
+            }
+
+            if let Some(category) = lobby.category_id.map(|id| ChannelId::from(id as u64)) {
+                let guild_channels = interaction.guild_id.unwrap().channels(ctx).await?;
+                if !guild_channels
+                    .values()
+                    .any(|channel| channel.parent_id == Some(category))
+                {
+                    category.delete(ctx).await.ok();
+                }
+            }
+
+            return Ok(());
+        }
+
+        self.respond(ctx, &interaction, format!("Lobby #{} deleted!", lobby.id))
+            .await
+    }
+
+    async fn start_lobby(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let mode = interaction
+            .data
+            .options
+            .first()
+            .unwrap()
+            .options
+            .iter()
+            .find(|option| option.name == "mode")
+            .and_then(|option| option.value.as_ref())
+            .and_then(|value| value.as_str())
+            .unwrap_or("role")
+            .to_string();
+        let teams_count = interaction
+            .data
+            .options
+            .first()
+            .unwrap()
+            .options
+            .iter()
+            .find(|option| option.name == "teams")
+            .and_then(|option| option.value.as_ref())
+            .and_then(|value| value.as_u64())
+            .unwrap_or(2) as usize;
+
+        let error = if !teams_count.is_multiple_of(2) {
+            Some("The number of teams must be even!")
+        } else if mode == "draft" && teams_count != 2 {
+            Some("Captain draft only supports two teams!")
+        } else {
+            None
+        };
+
+        if let Some(error) = error {
+            interaction
+                .create_interaction_response(ctx, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| message.content(error).ephemeral(true))
+                })
+                .await?;
+
+            return Ok(());
+        }
+
+        // a lobby given by number can be started from anywhere, e.g. for queue based lobbies
+        let guild_id = interaction.guild_id.unwrap();
+        let lobby = match self.resolved_option(&interaction, "lobby") {
+            Some(_) => match self.lobby_option(ctx, &interaction).await {
+                Some(lobby) => Some(lobby),
+                None => return self.respond(ctx, &interaction, "Lobby not found!").await,
+            },
+            None => None,
+        };
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        let lobby = match lobby {
+            Some(lobby) => lobby,
+            None => {
+                let member = guild_id.member(ctx, interaction.user.id).await?;
+
+                let channels = guild_id.channels(ctx).await?;
+
+                let mut is_in_lobby = false;
+                let mut channel_id = None;
+                for (id, channel) in channels {
+                    if channel.kind != ChannelType::Voice {
+                        continue;
+                    }
+                    let members = channel.members(ctx).await?;
+                    if members.iter().any(|m| m.user.id == member.user.id)
+                        && LobbyQuery::lobby_by_channel_id(db.connection(), guild_id, id)
+                            .await
+                            .is_some()
+                    {
+                        is_in_lobby = true;
+                        channel_id = Some(id);
+                        break;
+                    }
+                }
+
+                if !is_in_lobby {
+                    interaction
+                        .create_interaction_response(ctx, |response| {
+                            response
+                                .kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|message| {
+                                    message
+                                        .content("You are not in the mix lobby!")
+                                        .ephemeral(true)
+                                })
+                        })
+                        .await?;
+
+                    return Ok(());
+                }
+
+                LobbyQuery::lobby_by_channel_id(db.connection(), guild_id, channel_id.unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        drop(db);
+        drop(data);
+
+        let main_channel = ChannelId::from(lobby.main_voice_id as u64)
+            .to_channel(ctx)
+            .await
+            .ok()
+            .and_then(|channel| channel.guild());
+        if main_channel.is_none() {
+            interaction
+                .create_interaction_response(ctx, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            message
+                                .content("The channels of this lobby are missing!")
+                                .ephemeral(true)
+                        })
+                })
+                .await?;
+
+            return Ok(());
+        }
+
+        let Some(session) = Session::start(ctx, lobby.id).await else {
+            return self
+                .respond(ctx, &interaction, "This lobby is already mixing!")
+                .await;
+        };
+
+        let host = MixHost {
+            guild_id,
+            channel_id: interaction.channel_id,
+            author: Some(interaction.user.id),
+            interaction: Some(&interaction),
+            session: &session,
+        };
+        let result = async {
+            interaction
+                .create_interaction_response(ctx, |response| {
+                    response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                })
+                .await?;
+
+            self.run_mixes(ctx, &host, &lobby, &mode, teams_count).await
+        }
+        .await;
+
+        session.release(ctx).await;
+
+        result
+    }
+
+    pub async fn auto_start(&self, ctx: &Context, lobby: lobbies::Model) -> serenity::Result<()> {
+        let channel_id = match lobby.text_channel_id {
+            Some(id) => ChannelId::from(id as u64),
+            None => return Ok(()),
+        };
+
+        let needed = self.lobby_slots(&lobby).len() * 2;
+        if self.lobby_users(ctx, &lobby).await?.len() < needed {
+            return Ok(());
+        }
+        let Some(session) = Session::start(ctx, lobby.id).await else {
+            return Ok(());
+        };
+
+        let host = MixHost {
+            guild_id: GuildId::from(lobby.guild_id as u64),
+            channel_id,
+            author: None,
+            interaction: None,
+            session: &session,
+        };
+        let result = self.run_mixes(ctx, &host, &lobby, "role", 2).await;
+
+        session.release(ctx).await;
+
+        result
+    }
+
+    async fn ready_check(
+        &self,
+        ctx: &Context,
+        host: &MixHost<'_>,
+        lobby: &lobbies::Model,
+        users: &[UserId],
+        needed: Option<usize>,
+    ) -> serenity::Result<(HashSet<UserId>, bool)> {
+        let channel_id = lobby
+            .text_channel_id
+            .map(|id| ChannelId::from(id as u64))
+            .unwrap_or(host.channel_id);
+        let needed = needed.unwrap_or(users.len()).min(users.len());
+        let mentions = users.iter().map(|id| format!("<@{}>", id.0)).join(" ");
+
+        let message = channel_id
+            .send_message(ctx, |message| {
+                message
+                    .content(format!("{mentions}\nReady check: 0/{needed} players ready"))
+                    .components(|components| {
+                        components.create_action_row(|row| {
+                            row.create_button(|button| {
+                                button
+                                    .custom_id("ready")
+                                    .label("Ready")
+                                    .style(ButtonStyle::Success)
+                            })
+                        })
+                    })
+            })
+            .await?;
+
+        let mut collector = message
+            .await_component_interactions(ctx)
+            .timeout(Duration::from_secs(READY_CHECK_TIMEOUT))
+            .build();
+
+        let mut ready = HashSet::new();
+        while ready.len() < needed {
+            let interaction = match collector.next().await {
+                Some(interaction) => interaction,
+                None => break,
+            };
+            if !users.contains(&interaction.user.id) {
+                self.refuse(ctx, &interaction, "You are not part of this ready check!")
+                    .await;
+                continue;
+            }
+            if !ready.insert(interaction.user.id) {
+                self.acknowledge(ctx, &interaction).await;
+                continue;
+            }
+
+            interaction
+                .create_interaction_response(ctx, |response| {
+                    response
+                        .kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|message| {
+                            message.content(format!(
+                                "{mentions}\nReady check: {}/{needed} players ready",
+                                ready.len()
+                            ))
+                        })
+                })
+                .await?;
+        }
+
+        message.delete(ctx).await?;
+
+        let timed_out = ready.len() < needed;
+        Ok((ready, timed_out))
+    }
+
+    async fn lobby_users(
+        &self,
+        ctx: &Context,
+        lobby: &lobbies::Model,
+    ) -> serenity::Result<Vec<UserId>> {
+        if !lobby.use_queue {
+            return self
+                .voice_members(ctx, ChannelId::from(lobby.main_voice_id as u64))
+                .await;
+        }
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        Ok(QueueQuery::entries(db.connection(), lobby.id)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| UserId::from(entry.discord_id as u64))
+            .collect())
+    }
+
+    async fn dequeue(&self, ctx: &Context, lobby: &lobbies::Model, users: &[UserId]) {
+        if !lobby.use_queue || users.is_empty() {
+            return;
+        }
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        QueueQuery::remove(db.connection(), lobby.id, users).await;
+    }
+
+    async fn voice_members(
+        &self,
+        ctx: &Context,
+        channel_id: ChannelId,
+    ) -> serenity::Result<Vec<UserId>> {
+        Ok(match channel_id.to_channel(ctx).await?.guild() {
+            Some(channel) => channel
+                .members(ctx)
+                .await?
+                .into_iter()
+                .filter(|member| !member.user.bot)
+                .map(|member| member.user.id)
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+
+    async fn run_mixes(
+        &self,
+        ctx: &Context,
+        host: &MixHost<'_>,
+        lobby: &lobbies::Model,
+        mode: &str,
+        teams_count: usize,
+    ) -> serenity::Result<()> {
+        let mut rematch = false;
+        while self
+            .mix_lobby(ctx, host, lobby, mode, teams_count, rematch)
+            .await?
+        {
+            rematch = true;
+            // the lobby was free while waiting for the next game, someone else may have started
+            if !host.session.resume(ctx).await {
+                self.report(ctx, host, rematch, "This lobby is already mixing!")
+                    .await?;
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn mix_lobby(
+        &self,
+        ctx: &Context,
+        host: &MixHost<'_>,
+        lobby: &lobbies::Model,
+        mode: &str,
+        teams_count: usize,
+        rematch: bool,
+    ) -> serenity::Result<bool> {
+        let guild_id = host.guild_id;
+        let main_channel = ChannelId::from(lobby.main_voice_id as u64);
+
+        let mut team_channels = vec![
+            ChannelId::from(lobby.red_team_voice_id as u64),
+            ChannelId::from(lobby.blue_team_voice_id as u64),
+        ];
+        {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            for channels in LobbyQuery::match_channels(db.connection(), lobby.id)
+                .await
+                .unwrap_or_default()
+            {
+                team_channels.push(ChannelId::from(channels.red_team_voice_id as u64));
+                team_channels.push(ChannelId::from(channels.blue_team_voice_id as u64));
+            }
+        }
+
+        let mut moves = Vec::new();
+        for channel_id in team_channels {
+            let channel = channel_id
+                .to_channel(ctx)
+                .await
+                .ok()
+                .and_then(|channel| channel.guild());
+            if let Some(channel) = channel {
+                for member in channel.members(ctx).await? {
+                    moves.push((member.user.id, main_channel));
+                }
+            }
+        }
+        self.move_members(ctx, guild_id, moves).await;
+
+        let users = self.lobby_users(ctx, lobby).await?;
+
+        self.report(ctx, host, rematch, "Waiting for players to get ready...")
+            .await?;
+
+        // an automatic start needs a full lobby, a host can start with whoever is ready
+        let needed = host
+            .author
+            .is_none()
+            .then(|| self.lobby_slots(lobby).len() * 2);
+        let (ready, timed_out) = self.ready_check(ctx, host, lobby, &users, needed).await?;
+
+        // no-shows lose their place, otherwise they hold up every following ready check
+        if timed_out {
+            let no_shows = users
+                .iter()
+                .filter(|user_id| !ready.contains(user_id))
+                .copied()
+                .collect_vec();
+            self.dequeue(ctx, lobby, &no_shows).await;
+        }
+
+        if needed.is_some_and(|needed| ready.len() < needed) {
+            self.report(ctx, host, rematch, "Not enough players are ready!")
+                .await?;
+            return Ok(false);
+        }
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        let players = PlayerQuery::players_by_user_ids(db.connection(), users).await;
+
+        let models = match players {
+            Some(p) => p,
+            None => {
+                self.report(ctx, host, rematch, "Failed to get players")
+                    .await?;
+                return Ok(false);
+            }
+        };
+
+        for model in &models {
+            let user_id = UserId::from(model.discord_id as u64);
+            if ready.contains(&user_id) {
+                if model.no_shows > 0 {
+                    PlayerQuery::update_no_shows(db.connection(), user_id, 0).await;
+                }
+            } else if timed_out {
+                PlayerQuery::update_no_shows(db.connection(), user_id, model.no_shows + 1).await;
+            }
+        }
+
+        let models = models
+            .into_iter()
+            .filter(|model| ready.contains(&UserId::from(model.discord_id as u64)))
+            .collect_vec();
+
+        let players = models
+            .iter()
+            .cloned()
+            .map(Player::new)
+            .collect::<Vec<Player>>();
+        let slots = self.lobby_slots(lobby);
+
+        if mode == "draft" {
+            drop(db);
+            drop(data);
+
+            let interaction = match host.interaction {
+                Some(interaction) => interaction,
+                None => return Ok(false),
+            };
+            if !rematch {
+                interaction
+                    .delete_original_interaction_response(ctx)
+                    .await?;
+            }
+
+            let captains = [
+                self.user_option(interaction, "captain1"),
+                self.user_option(interaction, "captain2"),
+            ];
+            if let Some(teams) = self
+                .draft_teams(ctx, interaction, &players, slots, captains)
+                .await?
+            {
+                self.dequeue(ctx, lobby, &self.team_members(&teams.0, &teams.1, &players))
+                    .await;
+
+                let channels = (
+                    ChannelId::from(lobby.red_team_voice_id as u64),
+                    ChannelId::from(lobby.blue_team_voice_id as u64),
+                );
+                let tracker = MatchTracker::default();
+                return self
+                    .process_valid_teams(
+                        ctx,
+                        host,
+                        lobby,
+                        channels,
+                        None,
+                        teams,
+                        players,
+                        tracker.ticket(),
+                    )
+                    .await;
+            }
+
+            return Ok(false);
+        }
+
+        self.report(ctx, host, rematch, "Mixing teams...").await?;
+
+        let request = MixRequest {
+            mode: if mode == "open" {
+                MixMode::Open
+            } else {
+                MixMode::Role
+            },
+            slots,
+            matches: teams_count / 2,
+            now: Utc::now().naive_utc(),
+            seed: rand::random(),
+        };
+        let team_size = request.slots.len();
+        let matches = mixer::mix(&players, &request);
+        let mix = MixQuery::create(db.connection(), guild_id, lobby.id, &request, &models).await;
+
+        drop(db);
+        drop(data);
+
+        match matches {
+            Ok(matches) => {
+                let mixed = matches
+                    .iter()
+                    .flat_map(|(team1, team2)| self.team_members(team1, team2, &players))
+                    .collect_vec();
+                self.dequeue(ctx, lobby, &mixed).await;
+
+                let channels = self
+                    .match_channels(ctx, lobby, matches.len(), team_size)
+                    .await?;
+
+                if let Some(interaction) = host.interaction.filter(|_| !rematch) {
+                    interaction
+                        .delete_original_interaction_response(ctx)
+                        .await?;
+                }
+
+                let match_count = matches.len();
+                let tracker = MatchTracker::default();
+                let results = join_all(matches.into_iter().zip(channels).enumerate().map(
+                    |(i, (teams, channels))| {
+                        let title = [
+                            mix.as_ref().map(|mix| format!("Mix #{}", mix.id)),
+                            (match_count > 1).then(|| format!("Match {}", i + 1)),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .join(" - ");
+                        let title = Some(title).filter(|title| !title.is_empty());
+
+                        self.process_valid_teams(
+                            ctx,
+                            host,
+                            lobby,
+                            channels,
+                            title,
+                            teams,
+                            players.clone(),
+                            tracker.ticket(),
+                        )
+                    },
+                ))
+                .await;
+
+                let mut next_game = false;
+                for result in results {
+                    next_game |= result?;
+                }
+
+                Ok(next_game)
+            }
+            Err(why) => {
+                self.report(ctx, host, rematch, &why.to_string()).await?;
+
+                Ok(false)
+            }
+        }
+    }
+
+    async fn report(
+        &self,
+        ctx: &Context,
+        host: &MixHost<'_>,
+        rematch: bool,
+        content: &str,
+    ) -> serenity::Result<()> {
+        // the original response is gone once the first game was proposed
+        match host.interaction.filter(|_| !rematch) {
+            Some(interaction) => {
+                interaction
+                    .edit_original_interaction_response(ctx, |response| response.content(content))
+                    .await?;
+            }
+            None => {
+                host.channel_id
+                    .send_message(ctx, |message| message.content(content))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn replay_mix(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let id = interaction
+            .data
+            .options
+            .first()
+            .unwrap()
+            .options
+            .first()
+            .unwrap()
+            .value
+            .as_ref()
+            .unwrap()
+            .as_i64()
+            .unwrap();
+
+        let mix = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            MixQuery::mix_by_id(db.connection(), interaction.guild_id.unwrap(), id as i32).await
+        };
+
+        let inputs = mix.and_then(|mix| {
+            let lobby_id = mix.lobby_id;
+            let (request, models) = mixer::stored_inputs(mix)?;
+            Some((lobby_id, request, models))
+        });
+
+        let (lobby_id, request, models) = match inputs {
+            Some(inputs) => inputs,
+            None => {
+                interaction
+                    .create_interaction_response(ctx, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|message| {
+                                message.content("Mix not found!").ephemeral(true)
+                            })
+                    })
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        interaction
+            .create_interaction_response(ctx, |response| {
+                response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            })
+            .await?;
+
+        let players = models.into_iter().map(Player::new).collect_vec();
+        let matches = match mixer::mix(&players, &request) {
+            Ok(matches) => matches,
+            Err(why) => {
+                interaction
+                    .edit_original_interaction_response(ctx, |response| {
+                        response.content(format!("Mix #{id} could not be reproduced: {why}"))
+                    })
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let lobby = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            LobbyQuery::lobby_by_id(db.connection(), lobby_id).await
+        };
+
+        let mut attachments = Vec::new();
+        let mut embeds = Vec::new();
+        let mut notices = Vec::new();
+        for (i, (team1, team2)) in matches.iter().enumerate() {
+            match self
+                .draw_teams(
+                    ctx,
+                    interaction.guild_id.unwrap(),
+                    lobby.as_ref(),
+                    team1,
+                    team2,
+                    &players,
+                )
+                .await
+            {
+                Ok(image) => {
+                    notices.extend(image.notice.clone());
+                    attachments.push(image.into_attachment(&format!("teams{}", i + 1)))
+                }
+                Err(why) => {
+                    warn!("Could not render mix #{}: {}", id, why);
+                    let mut embed = CreateEmbed::default();
+                    Self::teams_embed(&mut embed, lobby.as_ref(), team1, team2, &players);
+                    embeds.push(embed);
+                }
+            }
+        }
+
+        interaction
+            .create_followup_message(ctx, |message| {
+                message
+                    .content(
+                        [format!("Mix #{} (seed {})", id, request.seed)]
+                            .into_iter()
+                            .chain(notices.into_iter().unique())
+                            .join("\n"),
+                    )
+                    .add_files(attachments)
+                    .add_embeds(embeds)
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn has_permission(
+        &self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> serenity::Result<bool> {
+        let guild = interaction.guild_id.unwrap().to_partial_guild(ctx).await?;
+        let member = guild.member(ctx, interaction.user.id).await?;
+
+        let mut has_permission = false;
+        if guild.owner_id == member.user.id {
+            has_permission = true;
+        }
+        if let Ok(perms) = member.permissions(ctx) {
+            if perms.administrator() {
+                has_permission = true;
+            }
+        }
+        if let Some(host_role) = LobbyHandler::host_role(ctx, guild.id).await {
+            if member.roles.contains(&host_role) {
+                has_permission = true;
+            }
+        }
+
+        Ok(has_permission)
+    }
+
+    async fn respond(
+        &self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+        content: impl ToString,
     ) -> serenity::Result<()> {
-        let (team1, team2) = teams.clone();
+        interaction
+            .create_interaction_response(ctx, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| message.content(content).ephemeral(true))
+            })
+            .await
+    }
 
-        let team1_names = team1
-            .players
-            .iter()
-            .sorted_by(|((a, _), _), ((b, _), _)| i32::from(a).cmp(&i32::from(b)))
-            .map(|(_, i)| async {
-                if let Ok(user) = players[i.unwrap()].discord_id.to_user(ctx).await {
-                    user.name
-                } else {
-                    players[i.unwrap()]
-                        .bn_name
-                        .clone()
-                        .unwrap_or("Unknown".to_string())
-                }
+    // every click has to be answered, otherwise discord shows it as failed
+    async fn acknowledge(&self, ctx: &Context, interaction: &MessageComponentInteraction) {
+        if let Err(why) = interaction
+            .create_interaction_response(ctx, |response| {
+                response.kind(InteractionResponseType::DeferredUpdateMessage)
+            })
+            .await
+        {
+            warn!(
+                "Could not acknowledge interaction {}: {}",
+                interaction.id, why
+            );
+        }
+    }
+
+    async fn refuse(
+        &self,
+        ctx: &Context,
+        interaction: &MessageComponentInteraction,
+        content: &str,
+    ) {
+        if let Err(why) = interaction
+            .create_interaction_response(ctx, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| message.content(content).ephemeral(true))
             })
+            .await
+        {
+            warn!("Could not answer interaction {}: {}", interaction.id, why);
+        }
+    }
+
+    fn resolved_option(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        name: &str,
+    ) -> Option<CommandDataOptionValue> {
+        interaction
+            .data
+            .options
+            .first()?
+            .options
+            .iter()
+            .find(|option| option.name == name)?
+            .resolved
+            .clone()
+    }
+
+    fn string_option(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        name: &str,
+    ) -> Option<String> {
+        match self.resolved_option(interaction, name) {
+            Some(CommandDataOptionValue::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn user_option(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        name: &str,
+    ) -> Option<UserId> {
+        match self.resolved_option(interaction, name) {
+            Some(CommandDataOptionValue::User(user, _)) => Some(user.id),
+            _ => None,
+        }
+    }
+
+    async fn lobby_option(
+        &self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Option<lobbies::Model> {
+        let id = match self.resolved_option(interaction, "lobby") {
+            Some(CommandDataOptionValue::Integer(id)) => id,
+            _ => return None,
+        };
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        LobbyQuery::lobby_by_id(db.connection(), id as i32)
+            .await
+            .filter(|lobby| lobby.guild_id == interaction.guild_id.unwrap().0 as i64)
+    }
+
+    fn lobby_slots(&self, lobby: &lobbies::Model) -> Vec<Role> {
+        [
+            (Role::Tank, lobby.tank_slots),
+            (Role::Dps, lobby.dps_slots),
+            (Role::Support, lobby.support_slots),
+        ]
+        .into_iter()
+        .flat_map(|(role, count)| std::iter::repeat_n(role, count.max(0) as usize))
+        .collect()
+    }
+
+    async fn draft_teams(
+        &self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+        players: &[Player],
+        slots: Vec<Role>,
+        captains: [Option<UserId>; 2],
+    ) -> serenity::Result<Option<(Team, Team)>> {
+        let mut captain_indices = captains
+            .into_iter()
+            .flatten()
+            .filter_map(|captain| players.iter().position(|p| p.discord_id == captain))
+            .unique()
             .collect_vec();
-        let team2_names = team2
-            .players
+
+        let by_rating = (0..players.len()).sorted_by(|a, b| {
+            let a = &players[*a];
+            let b = &players[*b];
+            b.rating(&b.open_queue_role())
+                .cmp(&a.rating(&a.open_queue_role()))
+        });
+        for index in by_rating {
+            if captain_indices.len() == 2 {
+                break;
+            }
+            if !captain_indices.contains(&index) {
+                captain_indices.push(index);
+            }
+        }
+
+        if captain_indices.len() < 2 {
+            interaction
+                .channel_id
+                .send_message(ctx, |message| {
+                    message.content("Not enough players for a captain draft!")
+                })
+                .await?;
+            return Ok(None);
+        }
+
+        let mut draft = Draft::new(
+            players.len(),
+            [captain_indices[0], captain_indices[1]],
+            slots,
+        );
+
+        let names = join_all(players.iter().map(|player| async {
+            if let Ok(user) = player.discord_id.to_user(ctx).await {
+                user.name
+            } else {
+                player.bn_name.clone().unwrap_or("Unknown".to_string())
+            }
+        }))
+        .await;
+
+        let mut message = interaction
+            .channel_id
+            .send_message(ctx, |message| message.content("Starting the draft..."))
+            .await?;
+
+        let mut rejected = false;
+        while let Some(team) = draft.current_team() {
+            let captain = players[draft.captains[team]].discord_id;
+            let available = draft
+                .available_players(team)
+                .into_iter()
+                .map(|index| (names[index].clone(), index.to_string()))
+                .collect_vec();
+            let prompt = if rejected {
+                format!(
+                    "<@{}>, that pick isn't possible, pick another player",
+                    captain.0
+                )
+            } else {
+                format!("<@{}>, pick a player", captain.0)
+            };
+
+            message
+                .edit(ctx, |message| {
+                    message
+                        .content(self.draft_summary(&draft, &names, prompt))
+                        .components(|components| {
+                            Self::select_menus(
+                                components,
+                                "draft_player",
+                                "Pick a player",
+                                available,
+                            )
+                        })
+                })
+                .await?;
+
+            let index = match self
+                .await_draft_selection(ctx, &message, captain)
+                .await
+                .and_then(|value| value.parse::<usize>().ok())
+            {
+                Some(index) => index,
+                None => return self.cancel_draft(ctx, message).await,
+            };
+
+            let roles = draft.open_roles(team);
+            message
+                .edit(ctx, |message| {
+                    message
+                        .content(self.draft_summary(
+                            &draft,
+                            &names,
+                            format!("<@{}>, pick a role for {}", captain.0, names[index]),
+                        ))
+                        .components(|components| {
+                            components.create_action_row(|row| {
+                                row.create_select_menu(|menu| {
+                                    menu.custom_id("draft_role")
+                                        .placeholder("Pick a role")
+                                        .options(|options| {
+                                            for role in &roles {
+                                                options.create_option(|option| {
+                                                    option.label(role).value(role)
+                                                });
+                                            }
+                                            options
+                                        })
+                                })
+                            })
+                        })
+                })
+                .await?;
+
+            let role = match self
+                .await_draft_selection(ctx, &message, captain)
+                .await
+                .and_then(|value| Role::try_from(value.as_str()).ok())
+            {
+                Some(role) => role,
+                None => return self.cancel_draft(ctx, message).await,
+            };
+
+            // the menus can be stale, so the draft has the final say on what is allowed
+            rejected = !draft.pick(team, index, &role);
+        }
+
+        message.delete(ctx).await?;
+
+        Ok(Some(draft.into_teams()))
+    }
+
+    fn draft_summary(&self, draft: &Draft, names: &[String], prompt: String) -> String {
+        let mut summary = String::from("**Captain draft**\n");
+
+        for (i, team) in draft.teams.iter().enumerate() {
+            let picks = team
+                .players
+                .iter()
+                .sorted_by(|((a, _), _), ((b, _), _)| i32::from(a).cmp(&i32::from(b)))
+                .filter_map(|((role, _), index)| {
+                    index.map(|index| format!("{} ({})", names[index], role))
+                })
+                .join(", ");
+
+            summary.push_str(&format!(
+                "Team {} ({}): {}\n",
+                i + 1,
+                names[draft.captains[i]],
+                picks
+            ));
+        }
+
+        summary + &prompt
+    }
+
+    async fn await_draft_selection(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        captain: UserId,
+    ) -> Option<String> {
+        let mut collector = message
+            .await_component_interactions(ctx)
+            .timeout(Duration::from_secs(3 * 60))
+            .build();
+
+        while let Some(interaction) = collector.next().await {
+            if interaction.user.id != captain {
+                self.refuse(ctx, &interaction, "It's not your pick!").await;
+                continue;
+            }
+
+            self.acknowledge(ctx, &interaction).await;
+            return interaction.data.values.first().cloned();
+        }
+
+        None
+    }
+
+    async fn cancel_draft(
+        &self,
+        ctx: &Context,
+        mut message: Message,
+    ) -> serenity::Result<Option<(Team, Team)>> {
+        message
+            .edit(ctx, |message| {
+                message
+                    .content("The draft timed out!")
+                    .components(|components| components)
+            })
+            .await?;
+
+        Ok(None)
+    }
+
+    async fn match_channels(
+        &self,
+        ctx: &Context,
+        lobby: &lobbies::Model,
+        matches: usize,
+        team_size: usize,
+    ) -> serenity::Result<Vec<(ChannelId, ChannelId)>> {
+        let guild_id = GuildId::from(lobby.guild_id as u64);
+        let red_channel = ChannelId::from(lobby.red_team_voice_id as u64);
+        let blue_channel = ChannelId::from(lobby.blue_team_voice_id as u64);
+        let category = red_channel
+            .to_channel(ctx)
+            .await
+            .ok()
+            .and_then(|channel| channel.guild())
+            .and_then(|channel| channel.parent_id);
+        let permissions = LobbyHandler::permissions(
+            guild_id,
+            ctx.cache.current_user_id(),
+            LobbyHandler::host_role(ctx, guild_id).await,
+            Permissions::CONNECT,
+        );
+
+        let mut channels = vec![(red_channel, blue_channel)];
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        let existing = LobbyQuery::match_channels(db.connection(), lobby.id)
+            .await
+            .unwrap_or_default();
+
+        for position in 1..matches as i32 {
+            if let Some(existing) = existing.iter().find(|c| c.position == position) {
+                channels.push((
+                    ChannelId::from(existing.red_team_voice_id as u64),
+                    ChannelId::from(existing.blue_team_voice_id as u64),
+                ));
+                continue;
+            }
+
+            let red_voice = guild_id
+                .create_channel(ctx, |c| {
+                    c.name(format!("{} {}", lobby.red_team_name, position + 1))
+                        .kind(ChannelType::Voice)
+                        .user_limit(team_size as u32)
+                        .permissions(permissions.clone());
+                    if let Some(category) = category {
+                        c.category(category);
+                    }
+                    c
+                })
+                .await?;
+            let blue_voice = guild_id
+                .create_channel(ctx, |c| {
+                    c.name(format!("{} {}", lobby.blue_team_name, position + 1))
+                        .kind(ChannelType::Voice)
+                        .user_limit(team_size as u32)
+                        .permissions(permissions.clone());
+                    if let Some(category) = category {
+                        c.category(category);
+                    }
+                    c
+                })
+                .await?;
+
+            LobbyQuery::create_match_channels(
+                db.connection(),
+                lobby.id,
+                position,
+                red_voice.id,
+                blue_voice.id,
+            )
+            .await;
+
+            channels.push((red_voice.id, blue_voice.id));
+        }
+
+        Ok(channels)
+    }
+
+    async fn draw_teams(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        lobby: Option<&lobbies::Model>,
+        team1: &Team,
+        team2: &Team,
+        players: &[Player],
+    ) -> Result<EncodedImage, RenderError> {
+        let team_names = Self::team_names(lobby);
+        let show_rd = lobby.is_some_and(|lobby| lobby.show_rd);
+        let avatars = ctx
+            .data
+            .read()
+            .await
+            .get::<AvatarCacheContainer>()
+            .unwrap()
+            .clone();
+
+        let mut teams = Vec::new();
+        for (team, name) in [team1, team2].into_iter().zip(team_names) {
+            let slots = join_all(
+                team.players
+                    .iter()
+                    .sorted_by(|((a, _), _), ((b, _), _)| i32::from(a).cmp(&i32::from(b)))
+                    .map(|((role, _), i)| {
+                        let avatars = avatars.clone();
+                        async move {
+                            let Some(i) = i else {
+                                return SlotImage {
+                                    role: *role,
+                                    name: None,
+                                    rating: None,
+                                    rd: None,
+                                    avatar: None,
+                                };
+                            };
+
+                            let player = &players[*i];
+                            let (name, avatar) = Self::player_card(ctx, &avatars, player).await;
+
+                            let rating = player.rating(role);
+                            SlotImage {
+                                role: *role,
+                                name: Some(name),
+                                rating: Some(rating.value as i32),
+                                rd: show_rd.then_some(rating.rd as i32),
+                                avatar,
+                            }
+                        }
+                    }),
+            )
+            .await;
+
+            teams.push(TeamImage {
+                name: name.to_string(),
+                rating: team.average_rating(players).value as i32,
+                slots,
+            });
+        }
+
+        // how much stronger the first team is on each role, only meaningful when both teams
+        // fill the same role slots (in open queue they usually don't)
+        let same_slots =
+            Role::iter().all(|role| team1.count_role(&role) == team2.count_role(&role));
+        let role_gaps = if same_slots {
+            Role::iter()
+                .filter(|role| team1.count_role(role) > 0)
+                .map(|role| {
+                    let gap = team1.average_rating_role(&role, players).value
+                        - team2.average_rating_role(&role, players).value;
+                    (role, gap as i32)
+                })
+                .collect_vec()
+        } else {
+            Vec::new()
+        };
+
+        let (theme, format) = Self::image_settings(ctx, guild_id).await;
+        let image_gen = Self::image_generator(ctx).await;
+
+        image_gen
+            .render(move |image_gen| {
+                image_gen.draw_teams_to_vec(theme.as_deref(), &teams, &role_gaps, format)
+            })
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn draw_result(
+        &self,
+        ctx: &Context,
+        lobby: &lobbies::Model,
+        participants: &[Participant],
+        players: &[Player],
+        ratings: &[(Rating, Option<Rating>)],
+        score: f32,
+        duration: Duration,
+    ) -> Result<EncodedImage, RenderError> {
+        let avatars = ctx
+            .data
+            .read()
+            .await
+            .get::<AvatarCacheContainer>()
+            .unwrap()
+            .clone();
+
+        let mut teams = Vec::new();
+        for (team, name) in [&lobby.blue_team_name, &lobby.red_team_name]
+            .into_iter()
+            .enumerate()
+        {
+            let results = join_all(
+                participants
+                    .iter()
+                    .zip(ratings)
+                    .filter(|(participant, _)| participant.team == team)
+                    .sorted_by_key(|(participant, _)| {
+                        (i32::from(&participant.role), participant.joined_at)
+                    })
+                    .map(|(participant, (old, new))| {
+                        let avatars = avatars.clone();
+                        async move {
+                            let (name, avatar) =
+                                Self::player_card(ctx, &avatars, &players[participant.index]).await;
+
+                            ResultPlayerImage {
+                                role: participant.role,
+                                name,
+                                avatar,
+                                rating: old.value as i32,
+                                new_rating: new.map(|rating| rating.value as i32),
+                            }
+                        }
+                    }),
+            )
+            .await;
+
+            teams.push(ResultTeamImage {
+                name: name.to_string(),
+                players: results,
+            });
+        }
+
+        let winner = match score {
+            score if score > 0.5 => Some(0),
+            score if score < 0.5 => Some(1),
+            _ => None,
+        };
+
+        let (theme, format) = Self::image_settings(ctx, GuildId::from(lobby.guild_id as u64)).await;
+        let image_gen = Self::image_generator(ctx).await;
+
+        image_gen
+            .render(move |image_gen| {
+                image_gen.draw_result_to_vec(
+                    theme.as_deref(),
+                    &ResultImage {
+                        teams,
+                        winner,
+                        duration,
+                    },
+                    format,
+                )
+            })
+            .await
+    }
+
+    // the text version of the result card
+    fn result_text(
+        lobby: &lobbies::Model,
+        participants: &[Participant],
+        players: &[Player],
+        ratings: &[(Rating, Option<Rating>)],
+        score: f32,
+    ) -> String {
+        let headline = match score {
+            score if score > 0.5 => format!("**{}** won", lobby.blue_team_name),
+            score if score < 0.5 => format!("**{}** won", lobby.red_team_name),
+            _ => "Draw".to_string(),
+        };
+
+        let changes = participants
             .iter()
-            .sorted_by(|((a, _), _), ((b, _), _)| i32::from(a).cmp(&i32::from(b)))
-            .map(|(_, i)| async {
-                if let Ok(user) = players[i.unwrap()].discord_id.to_user(ctx).await {
-                    user.name
-                } else {
-                    players[i.unwrap()]
-                        .bn_name
-                        .clone()
-                        .unwrap_or("Unknown".to_string())
+            .zip(ratings)
+            .map(|(participant, (old, new))| {
+                let player = &players[participant.index];
+                match new {
+                    Some(new) => format!(
+                        "{} <@{}> {} -> {} ({:+})",
+                        participant.role,
+                        player.discord_id.0,
+                        old.value as i32,
+                        new.value as i32,
+                        new.value as i32 - old.value as i32
+                    ),
+                    None => format!(
+                        "{} <@{}> {}",
+                        participant.role, player.discord_id.0, old.value as i32
+                    ),
                 }
             })
-            .collect_vec();
+            .join("\n");
 
-        let team1_names = join_all(team1_names).await;
-        let team2_names = join_all(team2_names).await;
+        format!("{}\n{}", headline, changes)
+    }
 
-        let image_data = {
-            let data = ctx.data.read().await;
-            let image_gen = data.get::<ImageGeneratorContainer>().unwrap();
+    async fn image_generator(ctx: &Context) -> Arc<ImageGenerator<'static>> {
+        ctx.data
+            .read()
+            .await
+            .get::<ImageGeneratorContainer>()
+            .unwrap()
+            .clone()
+    }
+
+    fn team_names(lobby: Option<&lobbies::Model>) -> [&str; 2] {
+        match lobby {
+            Some(lobby) => [lobby.blue_team_name.as_str(), lobby.red_team_name.as_str()],
+            None => ["Blue", "Red"],
+        }
+    }
+
+    // the text version of the teams image, also used when the image could not be rendered
+    fn teams_embed<'a>(
+        embed: &'a mut CreateEmbed,
+        lobby: Option<&lobbies::Model>,
+        team1: &Team,
+        team2: &Team,
+        players: &[Player],
+    ) -> &'a mut CreateEmbed {
+        for (team, name) in [team1, team2].into_iter().zip(Self::team_names(lobby)) {
+            let slots = team
+                .players
+                .iter()
+                .sorted_by(|((a, _), _), ((b, _), _)| i32::from(a).cmp(&i32::from(b)))
+                .map(|((role, _), i)| match i {
+                    Some(i) => format!(
+                        "{} <@{}> {}",
+                        role,
+                        players[*i].discord_id.0,
+                        players[*i].rating(role).value as i32
+                    ),
+                    None => format!("{} -", role),
+                })
+                .join("\n");
+
+            embed.field(
+                format!(
+                    "{} (average {})",
+                    name,
+                    team.average_rating(players).value as i32
+                ),
+                slots,
+                true,
+            );
+        }
+
+        embed
+    }
+
+    async fn image_settings(ctx: &Context, guild_id: GuildId) -> (Option<String>, OutputFormat) {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        match GuildQuery::guild_by_guild_id(db.connection(), guild_id).await {
+            Some(guild) => (guild.theme.clone(), OutputFormat::from(&guild)),
+            None => (None, OutputFormat::default()),
+        }
+    }
+
+    async fn proposal_format(ctx: &Context, guild_id: GuildId) -> ProposalFormat {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        GuildQuery::guild_by_guild_id(db.connection(), guild_id)
+            .await
+            .map_or(ProposalFormat::Image, |guild| guild.proposal_format)
+    }
 
-            let player_names = team1_names
-                .into_iter()
-                .chain(team2_names.into_iter())
-                .collect_vec();
+    // display name and avatar, falling back to the battle.net name for unknown users
+    async fn player_card(
+        ctx: &Context,
+        avatars: &AvatarCache,
+        player: &Player,
+    ) -> (String, Option<RgbaImage>) {
+        match player.discord_id.to_user(ctx).await {
+            Ok(user) => {
+                let avatar = avatars.avatar(&user).await;
+                (user.name, avatar)
+            }
+            Err(_) => (
+                player.bn_name.clone().unwrap_or("Unknown".to_string()),
+                None,
+            ),
+        }
+    }
 
-            let team1_rank = team1.average_rating(&players);
-            let team2_rank = team2.average_rating(&players);
+    #[allow(clippy::too_many_arguments)]
+    async fn process_valid_teams(
+        &self,
+        ctx: &Context,
+        host: &MixHost<'_>,
+        lobby: &lobbies::Model,
+        channels: (ChannelId, ChannelId),
+        title: Option<String>,
+        teams: (Team, Team),
+        players: Vec<Player>,
+        ticket: MatchTicket<'_>,
+    ) -> serenity::Result<bool> {
+        let (team1, team2) = teams.clone();
 
-            image_gen.draw_teams_to_vec(
-                player_names,
-                [team1_rank.value as i32, team2_rank.value as i32],
-                image::ImageOutputFormat::Png,
-            )
+        let format = Self::proposal_format(ctx, host.guild_id).await;
+        let image = match format {
+            ProposalFormat::Embed => None,
+            _ => match self
+                .draw_teams(ctx, host.guild_id, Some(lobby), &team1, &team2, &players)
+                .await
+            {
+                Ok(image) => Some(image),
+                Err(why) => {
+                    warn!("Could not render the teams of lobby {}: {}", lobby.id, why);
+                    None
+                }
+            },
         };
+        let notice = image.as_ref().and_then(|image| image.notice.clone());
+        let attachment = image.map(|image| image.into_attachment("teams"));
+        // a mix is never lost because of the image, the teams are sent as text instead
+        let embed = format != ProposalFormat::Image || attachment.is_none();
 
-        let attachment = AttachmentType::Bytes {
-            data: Cow::Owned(image_data),
-            filename: "teams.png".to_string(),
+        let channel_id = lobby
+            .text_channel_id
+            .map(|id| ChannelId::from(id as u64))
+            .unwrap_or(host.channel_id);
+        let mention = match host.author {
+            Some(author) => format!("<@{}>", author.0),
+            None => self.teams_mentions(&team1, &team2, &players),
         };
 
-        let msg = interaction
-            .channel_id
+        let msg = channel_id
             .send_message(ctx, |message| {
                 message
-                    .content(format!("<@{}>", interaction.user.id.0))
-                    .add_file(attachment)
+                    .content(
+                        [
+                            Some(match &title {
+                                Some(title) => format!("{} {}", mention, title),
+                                None => mention.clone(),
+                            }),
+                            notice.clone(),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .join("\n"),
+                    )
                     .components(|components| {
                         components.create_action_row(|row| {
                             row.create_button(|button| {
@@ -388,12 +2507,19 @@ impl LobbyCommand {
                                     .style(ButtonStyle::Success)
                             })
                         })
-                    })
-            })
-            .await?;
+                    });
 
-        interaction
-            .delete_original_interaction_response(ctx)
+                if let Some(attachment) = attachment {
+                    message.add_file(attachment);
+                }
+                if embed {
+                    message.embed(|embed| {
+                        Self::teams_embed(embed, Some(lobby), &team1, &team2, &players)
+                    });
+                }
+
+                message
+            })
             .await?;
 
         // interaction
@@ -428,29 +2554,48 @@ impl LobbyCommand {
         //     .unwrap();
 
         // let msg = interaction.get_interaction_response(ctx).await.unwrap();
-        let collector = msg
+        let mut collector = msg
             .await_component_interactions(ctx)
             .timeout(Duration::from_secs(10 * 60))
-            .guild_id(interaction.guild_id.unwrap())
-            .channel_id(interaction.channel_id)
-            .author_id(interaction.user.id)
-            .collect_limit(1)
+            .guild_id(host.guild_id)
+            .channel_id(channel_id)
             .build();
+        let allowed = match host.author {
+            Some(author) => vec![author],
+            // without a host any player of the match may answer
+            None => self.team_members(&team1, &team2, &players),
+        };
+
+        let mut answer = None;
+        while let Some(interaction) = collector.next().await {
+            if !allowed.contains(&interaction.user.id) {
+                self.refuse(ctx, &interaction, "You can't use these buttons!")
+                    .await;
+                continue;
+            }
+
+            self.acknowledge(ctx, &interaction).await;
+            answer = Some(interaction);
+            break;
+        }
 
-        let interactions = collector.collect::<Vec<_>>().await;
-        if let Some(interaction) = interactions.first() {
+        if let Some(interaction) = answer {
             match interaction.data.custom_id.as_str() {
                 "start" => {
-                    self.process_valid_teams_start(
-                        ctx,
-                        lobby,
-                        &team1,
-                        &team2,
-                        players,
-                        interaction.user.id,
-                        msg,
-                    )
-                    .await?
+                    return self
+                        .process_valid_teams_start(
+                            ctx,
+                            lobby,
+                            channels,
+                            &team1,
+                            &team2,
+                            players,
+                            interaction.user.id,
+                            msg,
+                            host.session,
+                            ticket,
+                        )
+                        .await;
                 }
                 "cancel" => {
                     self.process_valid_teams_cancel(ctx, &team1, &team2, msg)
@@ -466,89 +2611,101 @@ impl LobbyCommand {
             msg.delete(ctx).await?;
         }
 
-        Ok(())
+        Ok(false)
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn process_valid_teams_start(
         &self,
         ctx: &Context,
-        lobby: lobbies::Model,
+        lobby: &lobbies::Model,
+        channels: (ChannelId, ChannelId),
         team1: &Team,
         team2: &Team,
-        players: Vec<Player>,
+        mut players: Vec<Player>,
         author: UserId,
         mut message: Message,
-    ) -> serenity::Result<()> {
-        let main_channel = ChannelId::from(lobby.main_voice_id as u64)
-            .to_channel(ctx)
-            .await
-            .unwrap()
-            .guild()
-            .unwrap();
-        let red_channel = ChannelId::from(lobby.red_team_voice_id as u64)
-            .to_channel(ctx)
-            .await
-            .unwrap()
-            .guild()
-            .unwrap();
-        let blue_channel = ChannelId::from(lobby.blue_team_voice_id as u64)
-            .to_channel(ctx)
-            .await
-            .unwrap()
-            .guild()
-            .unwrap();
+        session: &Session,
+        mut ticket: MatchTicket<'_>,
+    ) -> serenity::Result<bool> {
+        let guild_id = GuildId::from(lobby.guild_id as u64);
+        let (red_channel, blue_channel) = channels;
+        let voice_states = ctx
+            .cache
+            .guild(guild_id)
+            .map(|guild| guild.voice_states)
+            .unwrap_or_default();
 
-        for member in main_channel.members(ctx).await? {
-            let index = players.iter().position(|p| p.discord_id == member.user.id);
+        LobbyHandler::transition(ctx, lobby.id, LobbyStatus::Proposing, LobbyStatus::InGame).await;
 
-            if team1
-                .players
-                .iter()
-                .any(|(_, i)| *i == index && index.is_some())
-            {
-                member.move_to_voice_channel(ctx, blue_channel.id).await?;
-            } else if team2
-                .players
-                .iter()
-                .any(|(_, i)| *i == index && index.is_some())
-            {
-                member.move_to_voice_channel(ctx, red_channel.id).await?;
+        let mut moves = Vec::new();
+        for (team, channel_id) in [(team1, blue_channel), (team2, red_channel)] {
+            for index in team.players.values().flatten() {
+                let user_id = players[*index].discord_id;
+
+                // queued players don't have to be connected to voice
+                if !lobby.use_queue
+                    || voice_states
+                        .get(&user_id)
+                        .and_then(|state| state.channel_id)
+                        .is_some()
+                {
+                    moves.push((user_id, channel_id));
+                }
             }
         }
+        let mut failed = self.move_members(ctx, guild_id, moves).await;
+
+        let started_at = Utc::now().naive_utc();
+        let mut teams = [team1.clone(), team2.clone()];
+        let mut participants = teams
+            .iter()
+            .enumerate()
+            .flat_map(|(team, slots)| {
+                slots
+                    .players
+                    .iter()
+                    .filter_map(move |((role, _), index)| index.map(|index| (team, *role, index)))
+            })
+            .map(|(team, role, index)| Participant {
+                team,
+                role,
+                index,
+                discord_id: players[index].discord_id,
+                joined_at: started_at,
+                left_at: None,
+            })
+            .collect_vec();
+
+        let game_id = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            let (team1, team2): (Vec<_>, Vec<_>) = participants
+                .iter()
+                .cloned()
+                .partition(|participant| participant.team == 0);
+            MatchQuery::create(
+                db.connection(),
+                guild_id,
+                lobby.id,
+                started_at,
+                &team1,
+                &team2,
+            )
+            .await
+            .map(|game| game.id)
+        };
 
+        let content = message.content.clone();
+        let mut notes = Vec::new();
         message
             .edit(ctx, |message| {
-                message.components(|components| {
-                    components
-                        .create_action_row(|row| {
-                            row.create_button(|button| {
-                                button
-                                    .custom_id("win_team1")
-                                    .label("Team 1 win")
-                                    .style(ButtonStyle::Success)
-                            })
-                            .create_button(|button| {
-                                button
-                                    .custom_id("draw")
-                                    .label("Draw")
-                                    .style(ButtonStyle::Secondary)
-                            })
-                            .create_button(|button| {
-                                button
-                                    .custom_id("win_team2")
-                                    .label("Team 2 win")
-                                    .style(ButtonStyle::Success)
-                            })
-                        })
-                        .create_action_row(|row| {
-                            row.create_button(|button| {
-                                button
-                                    .custom_id("cancel")
-                                    .label("Cancel game")
-                                    .style(ButtonStyle::Danger)
-                            })
-                        })
-                })
+                message
+                    .content(Self::match_summary(&content, &failed, &notes))
+                    .components(|components| {
+                        Self::result_components(components, !failed.is_empty())
+                    })
             })
             .await?;
 
@@ -578,89 +2735,612 @@ impl LobbyCommand {
         //     })
         // }).await?;
 
-        let collector = message
+        let mut collector = message
             .await_component_interactions(ctx)
             .timeout(Duration::from_secs(30 * 60))
             .guild_id(GuildId::from(lobby.guild_id as u64))
             .channel_id(message.channel_id)
-            .author_id(author)
-            .collect_limit(1)
             .build();
 
-        let interactions = collector.collect::<Vec<_>>().await;
-        if let Some(interaction) = interactions.first() {
+        let team_channels = [blue_channel, red_channel];
+        let mut leaving = None;
+        let mut result = None;
+        while let Some(interaction) = collector.next().await {
+            if interaction.user.id != author {
+                self.refuse(ctx, &interaction, "Only the host of this game can do that!")
+                    .await;
+                continue;
+            }
+
+            self.acknowledge(ctx, &interaction).await;
+            // the menus of a long list are numbered, they all do the same
+            let custom_id = interaction
+                .data
+                .custom_id
+                .split(':')
+                .next()
+                .unwrap_or_default();
+            match custom_id {
+                "retry_moves" => {
+                    failed = self.move_members(ctx, guild_id, failed).await;
+                }
+                "substitute" => {
+                    let mut options = Vec::new();
+                    for (i, participant) in participants.iter().enumerate() {
+                        if participant.is_playing() {
+                            let name = self.player_name(ctx, &players[participant.index]).await;
+                            options
+                                .push((format!("{} ({})", name, participant.role), i.to_string()));
+                        }
+                    }
+
+                    if let Err(why) = message
+                        .edit(ctx, |message| {
+                            message.components(|components| {
+                                Self::substitute_components(
+                                    components,
+                                    "sub_out",
+                                    "Pick the leaving player",
+                                    options,
+                                )
+                            })
+                        })
+                        .await
+                    {
+                        warn!(
+                            "Could not show the substitutes of lobby {}: {}",
+                            lobby.id, why
+                        );
+                    }
+                    continue;
+                }
+                "sub_out" => {
+                    leaving = interaction
+                        .data
+                        .values
+                        .first()
+                        .and_then(|value| value.parse::<usize>().ok())
+                        .filter(|i| participants.get(*i).is_some_and(|p| p.is_playing()));
+
+                    let main_channel = ChannelId::from(lobby.main_voice_id as u64);
+                    let waiting = match self.voice_members(ctx, main_channel).await {
+                        Ok(waiting) => waiting,
+                        Err(why) => {
+                            warn!("Could not get the members of lobby {}: {}", lobby.id, why);
+                            Vec::new()
+                        }
+                    };
+                    let mut options = Vec::new();
+                    for user_id in waiting {
+                        if options.len() == MAX_SELECT_OPTIONS * MAX_SELECT_MENUS {
+                            break;
+                        }
+                        if participants.iter().any(|p| p.discord_id == user_id) {
+                            continue;
+                        }
+                        let name = match user_id.to_user(ctx).await {
+                            Ok(user) => user.name,
+                            Err(_) => user_id.to_string(),
+                        };
+                        options.push((name, user_id.to_string()));
+                    }
+
+                    if leaving.is_some() && !options.is_empty() {
+                        match message
+                            .edit(ctx, |message| {
+                                message.components(|components| {
+                                    Self::substitute_components(
+                                        components,
+                                        "sub_in",
+                                        "Pick the replacement",
+                                        options,
+                                    )
+                                })
+                            })
+                            .await
+                        {
+                            Ok(_) => continue,
+                            Err(why) => warn!(
+                                "Could not show the replacements of lobby {}: {}",
+                                lobby.id, why
+                            ),
+                        }
+                    }
+
+                    notes.push("Nobody in the main channel can substitute!".to_string());
+                }
+                "sub_in" => {
+                    let joining = interaction
+                        .data
+                        .values
+                        .first()
+                        .and_then(|value| value.parse::<u64>().ok())
+                        .map(UserId::from);
+
+                    if let (Some(leaving), Some(joining)) = (leaving.take(), joining) {
+                        if let Some(substitution) = self
+                            .substitute(
+                                ctx,
+                                game_id,
+                                &mut teams,
+                                &mut players,
+                                &mut participants,
+                                leaving,
+                                joining,
+                            )
+                            .await
+                        {
+                            notes.push(format!(
+                                "<@{}> replaced <@{}>",
+                                substitution.joining.0, substitution.leaving.0
+                            ));
+                            failed.extend(
+                                self.move_members(
+                                    ctx,
+                                    guild_id,
+                                    vec![(joining, team_channels[substitution.team])],
+                                )
+                                .await,
+                            );
+                        }
+                    }
+                }
+                "sub_back" => {
+                    leaving = None;
+                }
+                _ => {
+                    result = Some(interaction);
+                    break;
+                }
+            }
+
+            // the result can still be reported even if the message couldn't be updated
+            if let Err(why) = message
+                .edit(ctx, |message| {
+                    message
+                        .content(Self::match_summary(&content, &failed, &notes))
+                        .components(|components| {
+                            Self::result_components(components, !failed.is_empty())
+                        })
+                })
+                .await
+            {
+                warn!("Could not update the match of lobby {}: {}", lobby.id, why);
+            }
+        }
+
+        if let Some(interaction) = result {
             let mut score = 0.5f32;
             match interaction.data.custom_id.as_str() {
                 "win_team1" => score = 1.0,
                 "draw" => score = 0.5,
                 "win_team2" => score = 0.0,
                 "cancel" => {
-                    return message.delete(ctx).await;
+                    self.finish_match(ctx, game_id, None).await;
+                    if lobby.return_to_main {
+                        self.return_to_main(ctx, lobby, channels).await?;
+                    }
+                    message.delete(ctx).await?;
+                    return Ok(false);
                     // return interaction.delete_original_interaction_response(ctx).await;
                 }
                 _ => {}
             }
 
-            let team1_average_rating = team1.average_rating(&players);
-            let team2_average_rating = team2.average_rating(&players);
+            // the session was reset while the match was played
+            let status = ticket.report();
+            if !LobbyHandler::transition(ctx, lobby.id, LobbyStatus::InGame, status).await {
+                message.delete(ctx).await?;
+                return Ok(false);
+            }
 
-            let team1 = team1
-                .players
-                .clone()
-                .into_iter()
-                .filter_map(|((role, _), player)| player.map(|id| (role, id)))
-                .collect::<Vec<_>>();
-            let team2 = team2
-                .players
-                .clone()
-                .into_iter()
-                .filter_map(|((role, _), player)| player.map(|id| (role, id)))
-                .collect::<Vec<_>>();
+            let finished_at = self.finish_match(ctx, game_id, Some(score)).await;
+            let average_ratings = [
+                teams[0].average_rating(&players),
+                teams[1].average_rating(&players),
+            ];
 
             let data = ctx.data.read().await;
             let db = data.get::<DatabaseContainer>().unwrap().read().await;
 
-            for (role, index) in team1 {
-                let mut rating = players[index].ranks[&role];
-                rating.update(&team2_average_rating, score);
-                PlayerQuery::update_rating(
-                    db.connection(),
-                    players[index].discord_id,
-                    role,
-                    rating,
-                )
-                .await;
-                PlayerQuery::update_last_played(
-                    db.connection(),
-                    players[index].discord_id,
-                    Utc::now().naive_utc(),
-                )
-                .await;
+            let mut ratings = Vec::new();
+            for participant in &participants {
+                let player = &players[participant.index];
+                let (opponents, score) = match participant.team {
+                    0 => (average_ratings[1], score),
+                    _ => (average_ratings[0], 1.0 - score),
+                };
+
+                // players who were only there for a short part of the match aren't rated
+                let share = participant.played_share(started_at, finished_at);
+                // the blended estimate the teams were balanced and shown with, like the opponents'
+                // averages, so an uncalibrated off-role starts from what the main role suggests
+                let old_rating = player.rating(&participant.role);
+                if share >= MIN_PLAYED_SHARE {
+                    let mut rating = old_rating;
+                    rating.update_weighted(&opponents, score, share);
+                    PlayerQuery::update_rating(
+                        db.connection(),
+                        player.discord_id,
+                        participant.role,
+                        rating,
+                    )
+                    .await;
+                    PlayerQuery::add_result(db.connection(), player.discord_id, score).await;
+                    ratings.push((old_rating, Some(rating)));
+                } else {
+                    ratings.push((old_rating, None));
+                }
+                PlayerQuery::update_last_played(db.connection(), player.discord_id, finished_at)
+                    .await;
             }
 
-            for (role, index) in team2 {
-                let mut rating = players[index].ranks[&role];
-                rating.update(&team1_average_rating, 1.0 - score);
-                PlayerQuery::update_rating(
-                    db.connection(),
-                    players[index].discord_id,
-                    role,
-                    rating,
+            drop(db);
+            drop(data);
+
+            // the result card stays in the channel as a record of the game
+            let result = self
+                .draw_result(
+                    ctx,
+                    lobby,
+                    &participants,
+                    &players,
+                    &ratings,
+                    score,
+                    (finished_at - started_at).to_std().unwrap_or_default(),
                 )
                 .await;
-                PlayerQuery::update_last_played(
-                    db.connection(),
-                    players[index].discord_id,
-                    Utc::now().naive_utc(),
-                )
+            message
+                .channel_id
+                .send_message(ctx, |message| match result {
+                    Ok(image) => {
+                        if let Some(notice) = &image.notice {
+                            message.content(notice);
+                        }
+                        message.add_file(image.into_attachment("result"))
+                    }
+                    Err(why) => {
+                        warn!("Could not render the result of lobby {}: {}", lobby.id, why);
+                        message
+                            .content(Self::result_text(
+                                lobby,
+                                &participants,
+                                &players,
+                                &ratings,
+                                score,
+                            ))
+                            .allowed_mentions(|mentions| mentions.empty_users())
+                    }
+                })
+                .await?;
+
+            // the result is saved, the lobby doesn't have to wait for the next game
+            if status == LobbyStatus::Reporting {
+                session.release(ctx).await;
+            }
+        } else {
+            self.finish_match(ctx, game_id, None).await;
+            message.delete(ctx).await?;
+            return Ok(false);
+        }
+
+        message
+            .edit(ctx, |message| {
+                message.components(|components| {
+                    components.create_action_row(|row| {
+                        row.create_button(|button| {
+                            button
+                                .custom_id("next_game")
+                                .label("Next game")
+                                .style(ButtonStyle::Primary)
+                        })
+                    })
+                })
+            })
+            .await?;
+
+        // pressing "Next game" before the delay is over gathers everyone right away
+        let mut next_game = false;
+        if lobby.return_to_main {
+            next_game = self
+                .await_next_game(ctx, &message, author, lobby.return_delay as u64)
                 .await;
+            if !next_game {
+                self.return_to_main(ctx, lobby, channels).await?;
             }
+        }
+        if !next_game {
+            next_game = self.await_next_game(ctx, &message, author, 10 * 60).await;
+        }
 
-            drop(db);
-            drop(data);
+        message.delete(ctx).await?;
+
+        Ok(next_game)
+    }
+
+    async fn await_next_game(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        author: UserId,
+        timeout: u64,
+    ) -> bool {
+        let mut collector = message
+            .await_component_interactions(ctx)
+            .timeout(Duration::from_secs(timeout))
+            .build();
+
+        while let Some(interaction) = collector.next().await {
+            if interaction.user.id != author {
+                self.refuse(ctx, &interaction, "Only the host of this game can do that!")
+                    .await;
+                continue;
+            }
+
+            self.acknowledge(ctx, &interaction).await;
+            return interaction.data.custom_id == "next_game";
         }
 
-        message.delete(ctx).await
+        false
+    }
+
+    async fn return_to_main(
+        &self,
+        ctx: &Context,
+        lobby: &lobbies::Model,
+        channels: (ChannelId, ChannelId),
+    ) -> serenity::Result<()> {
+        let guild_id = GuildId::from(lobby.guild_id as u64);
+        let main_channel = ChannelId::from(lobby.main_voice_id as u64);
+        let (red_channel, blue_channel) = channels;
+
+        let mut moves = Vec::new();
+        for channel_id in [red_channel, blue_channel] {
+            if let Some(channel) = channel_id.to_channel(ctx).await?.guild() {
+                for member in channel.members(ctx).await? {
+                    moves.push((member.user.id, main_channel));
+                }
+            }
+        }
+        self.move_members(ctx, guild_id, moves).await;
+
+        Ok(())
+    }
+
+    async fn move_members(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        moves: Vec<(UserId, ChannelId)>,
+    ) -> Vec<(UserId, ChannelId)> {
+        let mut failed = moves;
+
+        for attempt in 0..MOVE_ATTEMPTS {
+            if failed.is_empty() {
+                break;
+            }
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_secs(MOVE_RETRY_DELAY)).await;
+            }
+
+            let results = join_all(
+                failed
+                    .iter()
+                    .map(|(user_id, channel_id)| guild_id.move_member(ctx, *user_id, *channel_id)),
+            )
+            .await;
+
+            failed = failed
+                .into_iter()
+                .zip(results)
+                .filter_map(|((user_id, channel_id), result)| match result {
+                    Ok(_) => None,
+                    Err(why) => {
+                        warn!("Failed to move {} to {}: {}", user_id, channel_id, why);
+                        Some((user_id, channel_id))
+                    }
+                })
+                .collect();
+        }
+
+        failed
+    }
+
+    fn match_summary(content: &str, failed: &[(UserId, ChannelId)], notes: &[String]) -> String {
+        let mut summary = content.to_string();
+
+        for note in notes {
+            summary.push_str(&format!("\n{}", note));
+        }
+        if !failed.is_empty() {
+            summary.push_str(&format!(
+                "\nCould not move: {}",
+                failed
+                    .iter()
+                    .map(|(user_id, _)| format!("<@{}>", user_id.0))
+                    .join(", ")
+            ));
+        }
+
+        summary
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn substitute(
+        &self,
+        ctx: &Context,
+        game_id: Option<i32>,
+        teams: &mut [Team; 2],
+        players: &mut Vec<Player>,
+        participants: &mut Vec<Participant>,
+        leaving: usize,
+        joining: UserId,
+    ) -> Option<Substitution> {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        let model = PlayerQuery::create_if_not_exists(db.connection(), joining).await?;
+        players.push(Player::new(model));
+        let index = players.len() - 1;
+
+        let now = Utc::now().naive_utc();
+        let participant = &mut participants[leaving];
+        participant.left_at = Some(now);
+
+        let substitution = Substitution {
+            team: participant.team,
+            role: participant.role,
+            leaving: participant.discord_id,
+            joining,
+            at: now,
+        };
+        if let Some(slot) = teams[participant.team]
+            .players
+            .values_mut()
+            .find(|slot| **slot == Some(participant.index))
+        {
+            *slot = Some(index);
+        }
+
+        participants.push(Participant {
+            team: substitution.team,
+            role: substitution.role,
+            index,
+            discord_id: joining,
+            joined_at: now,
+            left_at: None,
+        });
+
+        if let Some(game_id) = game_id {
+            MatchQuery::add_substitution(db.connection(), game_id, &substitution).await;
+        }
+
+        Some(substitution)
+    }
+
+    async fn finish_match(
+        &self,
+        ctx: &Context,
+        game_id: Option<i32>,
+        score: Option<f32>,
+    ) -> DateTime {
+        let finished_at = Utc::now().naive_utc();
+
+        if let Some(game_id) = game_id {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            MatchQuery::finish(db.connection(), game_id, score, finished_at).await;
+        }
+
+        finished_at
+    }
+
+    async fn player_name(&self, ctx: &Context, player: &Player) -> String {
+        if let Ok(user) = player.discord_id.to_user(ctx).await {
+            user.name
+        } else {
+            player.bn_name.clone().unwrap_or("Unknown".to_string())
+        }
+    }
+
+    fn substitute_components<'a>(
+        components: &'a mut CreateComponents,
+        custom_id: &str,
+        placeholder: &str,
+        options: Vec<(String, String)>,
+    ) -> &'a mut CreateComponents {
+        Self::select_menus(components, custom_id, placeholder, options).create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .custom_id("sub_back")
+                    .label("Back")
+                    .style(ButtonStyle::Secondary)
+            })
+        })
+    }
+
+    // a menu holds at most 25 options, longer lists are split into numbered menus
+    fn select_menus<'a>(
+        components: &'a mut CreateComponents,
+        custom_id: &str,
+        placeholder: &str,
+        options: Vec<(String, String)>,
+    ) -> &'a mut CreateComponents {
+        let pages = options
+            .chunks(MAX_SELECT_OPTIONS)
+            .take(MAX_SELECT_MENUS)
+            .collect_vec();
+        for (i, page) in pages.iter().enumerate() {
+            components.create_action_row(|row| {
+                row.create_select_menu(|menu| {
+                    if pages.len() > 1 {
+                        menu.custom_id(format!("{}:{}", custom_id, i))
+                            .placeholder(format!("{} ({}/{})", placeholder, i + 1, pages.len()));
+                    } else {
+                        menu.custom_id(custom_id).placeholder(placeholder);
+                    }
+                    menu.options(|menu_options| {
+                        for (label, value) in page.iter() {
+                            menu_options.create_option(|option| option.label(label).value(value));
+                        }
+                        menu_options
+                    })
+                })
+            });
+        }
+
+        components
+    }
+
+    fn result_components(
+        components: &mut CreateComponents,
+        retry_moves: bool,
+    ) -> &mut CreateComponents {
+        components
+            .create_action_row(|row| {
+                row.create_button(|button| {
+                    button
+                        .custom_id("win_team1")
+                        .label("Team 1 win")
+                        .style(ButtonStyle::Success)
+                })
+                .create_button(|button| {
+                    button
+                        .custom_id("draw")
+                        .label("Draw")
+                        .style(ButtonStyle::Secondary)
+                })
+                .create_button(|button| {
+                    button
+                        .custom_id("win_team2")
+                        .label("Team 2 win")
+                        .style(ButtonStyle::Success)
+                })
+            })
+            .create_action_row(|row| {
+                row.create_button(|button| {
+                    button
+                        .custom_id("cancel")
+                        .label("Cancel game")
+                        .style(ButtonStyle::Danger)
+                });
+                row.create_button(|button| {
+                    button
+                        .custom_id("substitute")
+                        .label("Substitute")
+                        .style(ButtonStyle::Secondary)
+                });
+                if retry_moves {
+                    row.create_button(|button| {
+                        button
+                            .custom_id("retry_moves")
+                            .label("Retry moves")
+                            .style(ButtonStyle::Primary)
+                    });
+                }
+                row
+            })
     }
 
     async fn process_valid_teams_cancel(
@@ -682,4 +3362,21 @@ impl LobbyCommand {
     ) -> serenity::Result<()> {
         Ok(())
     }
+
+    fn team_members(&self, team1: &Team, team2: &Team, players: &[Player]) -> Vec<UserId> {
+        team1
+            .players
+            .values()
+            .chain(team2.players.values())
+            .flatten()
+            .map(|index| players[*index].discord_id)
+            .collect()
+    }
+
+    fn teams_mentions(&self, team1: &Team, team2: &Team, players: &[Player]) -> String {
+        self.team_members(team1, team2, players)
+            .into_iter()
+            .map(|id| format!("<@{}>", id.0))
+            .join(" ")
+    }
 }
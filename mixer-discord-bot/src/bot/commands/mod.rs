@@ -1,7 +1,10 @@
 pub mod creator;
+pub mod leaderboard;
 pub mod lobby;
 pub mod ping;
 pub mod preference;
+pub mod profile;
+pub mod queue;
 pub mod rating;
 pub mod settings;
 
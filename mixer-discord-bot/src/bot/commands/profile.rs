@@ -0,0 +1,178 @@
+use itertools::Itertools;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+use serenity::model::application::interaction::{
+    application_command::ApplicationCommandInteraction, InteractionResponseType,
+};
+use tracing::log::warn;
+
+use crate::bot::commands::MixerCommand;
+use crate::database::queries::prelude::*;
+use crate::database::DatabaseContainer;
+use crate::image_manipulation::avatar::AvatarCacheContainer;
+use crate::image_manipulation::encoding::OutputFormat;
+use crate::image_manipulation::{ImageGeneratorContainer, ProfileImage, ProfileRole};
+use crate::mixer::player::Player;
+use entity::prelude::Role;
+
+#[derive(Clone)]
+pub struct ProfileCommand;
+
+#[async_trait]
+impl MixerCommand for ProfileCommand {
+    fn name(&self) -> String {
+        "profile".to_string()
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name(self.name())
+            .description("Show the ratings and results of a player")
+            .create_option(|option| {
+                option
+                    .name("user")
+                    .description("The player to show, defaults to you")
+                    .kind(CommandOptionType::User)
+            })
+            .dm_permission(false);
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let user = match interaction
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "user")
+            .and_then(|option| option.resolved.as_ref())
+        {
+            Some(CommandDataOptionValue::User(user, _)) => user.clone(),
+            _ => interaction.user.clone(),
+        };
+
+        let (guild, model) = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            (
+                GuildQuery::guild_by_guild_id(db.connection(), interaction.guild_id.unwrap()).await,
+                PlayerQuery::player_by_user_id(db.connection(), user.id).await,
+            )
+        };
+        let theme = guild.as_ref().and_then(|guild| guild.theme.clone());
+        let format = guild.as_ref().map(OutputFormat::from).unwrap_or_default();
+
+        let Some(model) = model else {
+            interaction
+                .create_interaction_response(ctx, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            message
+                                .content(format!("<@{}> has no profile yet", user.id))
+                                .allowed_mentions(|mentions| mentions.empty_users())
+                                .ephemeral(true)
+                        })
+                })
+                .await?;
+            return Ok(());
+        };
+
+        interaction
+            .create_interaction_response(ctx, |response| {
+                response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            })
+            .await?;
+
+        let (wins, losses, draws) = (model.wins, model.losses, model.draws);
+        let player = Player::new(model);
+
+        let roles = [Role::Tank, Role::Dps, Role::Support]
+            .into_iter()
+            .map(|role| {
+                let rating = player.ranks[&role];
+                ProfileRole {
+                    role,
+                    rating: rating.value as i32,
+                    rd: rating.rd as i32,
+                    preference: player
+                        .priority_roles
+                        .iter()
+                        .position(|priority| *priority == Some(role)),
+                }
+            })
+            .collect();
+
+        let avatar = {
+            let avatars = ctx
+                .data
+                .read()
+                .await
+                .get::<AvatarCacheContainer>()
+                .unwrap()
+                .clone();
+
+            avatars.avatar(&user).await
+        };
+
+        let profile = ProfileImage {
+            name: user.name.clone(),
+            avatar,
+            roles,
+            wins,
+            losses,
+            draws,
+            flex: player.flex,
+        };
+        let text = profile_text(&profile);
+
+        let image_gen = ctx
+            .data
+            .read()
+            .await
+            .get::<ImageGeneratorContainer>()
+            .unwrap()
+            .clone();
+        let result = image_gen
+            .render(move |image_gen| {
+                image_gen.draw_profile_to_vec(theme.as_deref(), &profile, format)
+            })
+            .await;
+
+        interaction
+            .create_followup_message(ctx, |message| match result {
+                Ok(image) => {
+                    if let Some(notice) = &image.notice {
+                        message.content(notice);
+                    }
+                    message.add_file(image.into_attachment("profile"))
+                }
+                Err(why) => {
+                    warn!("Could not render the profile of {}: {}", user.id, why);
+                    message.content(text)
+                }
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn profile_text(profile: &ProfileImage) -> String {
+    let roles = profile
+        .roles
+        .iter()
+        .map(|role| format!("{} {} ({})", role.role, role.rating, role.rd))
+        .join("\n");
+
+    format!(
+        "**{}**\n{}\n{}W {}L {}D",
+        profile.name, roles, profile.wins, profile.losses, profile.draws
+    )
+}
@@ -0,0 +1,289 @@
+use itertools::Itertools;
+use serenity::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::{
+    application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+    InteractionResponseType,
+};
+use sqlx::types::chrono::Utc;
+use tracing::log::warn;
+
+use crate::bot::commands::lobby::LobbyCommand;
+use crate::bot::commands::MixerCommand;
+use crate::database::queries::prelude::*;
+use crate::database::DatabaseContainer;
+use entity::lobbies;
+
+#[derive(Clone)]
+pub struct QueueCommand;
+
+#[async_trait]
+impl MixerCommand for QueueCommand {
+    fn name(&self) -> String {
+        "queue".to_string()
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .name(self.name())
+            .description("Queue up for a lobby without joining its voice channel")
+            .create_option(|option| {
+                option
+                    .name("join")
+                    .description("Join the queue of a lobby")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("lobby")
+                            .description("The number of the lobby")
+                            .kind(CommandOptionType::Integer)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("leave")
+                    .description("Leave the queue")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("lobby")
+                            .description("The number of the lobby, all queues if empty")
+                            .kind(CommandOptionType::Integer)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("status")
+                    .description("Show who is in the queue")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("lobby")
+                            .description("The number of the lobby")
+                            .kind(CommandOptionType::Integer)
+                    })
+            })
+            .dm_permission(false);
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        match interaction.data.options.first().unwrap().name.as_str() {
+            "join" => self.join_queue(ctx, interaction).await,
+            "leave" => self.leave_queue(ctx, interaction).await,
+            "status" => self.queue_status(ctx, interaction).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+impl QueueCommand {
+    async fn join_queue(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let lobby = match self.queue_lobby(ctx, &interaction).await {
+            Ok(lobby) => lobby,
+            Err(error) => return self.respond(ctx, &interaction, error).await,
+        };
+
+        let joined = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            if QueueQuery::entry_by_user_id(db.connection(), lobby.id, interaction.user.id)
+                .await
+                .is_some()
+            {
+                Err("You are already in this queue!")
+            } else {
+                PlayerQuery::create_if_not_exists(db.connection(), interaction.user.id).await;
+                match QueueQuery::join(
+                    db.connection(),
+                    lobby.id,
+                    interaction.user.id,
+                    Utc::now().naive_utc(),
+                )
+                .await
+                {
+                    Some(_) => Ok(QueueQuery::entries(db.connection(), lobby.id)
+                        .await
+                        .unwrap_or_default()
+                        .len()),
+                    None => Err("Failed to join the queue!"),
+                }
+            }
+        };
+
+        let queued = match joined {
+            Ok(queued) => queued,
+            Err(error) => return self.respond(ctx, &interaction, error).await,
+        };
+
+        self.respond(
+            ctx,
+            &interaction,
+            format!(
+                "You joined the queue of lobby #{} ({} queued)",
+                lobby.id, queued
+            ),
+        )
+        .await?;
+
+        if lobby.auto_start {
+            if let Err(why) = LobbyCommand.auto_start(ctx, lobby).await {
+                warn!("Auto start failed: {}", why);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn leave_queue(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let guild_id = interaction.guild_id.unwrap();
+        let lobby_id = self.lobby_option(&interaction);
+
+        let left = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            let mut left = false;
+            for lobby in LobbyQuery::lobbies_by_guild_id(db.connection(), guild_id)
+                .await
+                .unwrap_or_default()
+            {
+                if lobby_id.is_none_or(|id| id == lobby.id) {
+                    left |= QueueQuery::leave(db.connection(), lobby.id, interaction.user.id).await;
+                }
+            }
+
+            left
+        };
+
+        let content = if left {
+            "You left the queue"
+        } else {
+            "You are not in the queue!"
+        };
+        self.respond(ctx, &interaction, content).await
+    }
+
+    async fn queue_status(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+    ) -> serenity::Result<()> {
+        let lobby = match self.queue_lobby(ctx, &interaction).await {
+            Ok(lobby) => lobby,
+            Err(error) => return self.respond(ctx, &interaction, error).await,
+        };
+
+        let entries = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            QueueQuery::entries(db.connection(), lobby.id)
+                .await
+                .unwrap_or_default()
+        };
+
+        let needed = (lobby.tank_slots + lobby.dps_slots + lobby.support_slots) * 2;
+        let content = if entries.is_empty() {
+            format!("The queue of lobby #{} is empty (0/{})", lobby.id, needed)
+        } else {
+            format!(
+                "Queue of lobby #{} ({}/{}):\n{}",
+                lobby.id,
+                entries.len(),
+                needed,
+                entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| format!(
+                        "{}. <@{}> <t:{}:R>",
+                        i + 1,
+                        entry.discord_id,
+                        entry.joined_at.timestamp()
+                    ))
+                    .join("\n")
+            )
+        };
+
+        self.respond(ctx, &interaction, content).await
+    }
+
+    async fn queue_lobby(
+        &self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<lobbies::Model, String> {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        let lobbies =
+            LobbyQuery::lobbies_by_guild_id(db.connection(), interaction.guild_id.unwrap())
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|lobby| lobby.use_queue)
+                .collect_vec();
+
+        if let Some(id) = self.lobby_option(interaction) {
+            return lobbies
+                .into_iter()
+                .find(|lobby| lobby.id == id)
+                .ok_or_else(|| format!("Lobby #{} doesn't have a queue!", id));
+        }
+
+        match lobbies.len() {
+            0 => Err("There are no lobbies with a queue on this server!".to_string()),
+            1 => Ok(lobbies.into_iter().next().unwrap()),
+            _ => Err(format!(
+                "Please choose a lobby: {}",
+                lobbies
+                    .iter()
+                    .map(|lobby| format!("#{} {}", lobby.id, lobby.name))
+                    .join(", ")
+            )),
+        }
+    }
+
+    fn lobby_option(&self, interaction: &ApplicationCommandInteraction) -> Option<i32> {
+        interaction
+            .data
+            .options
+            .first()?
+            .options
+            .iter()
+            .find(|option| option.name == "lobby")
+            .and_then(|option| match option.resolved.as_ref() {
+                Some(CommandDataOptionValue::Integer(id)) => Some(*id as i32),
+                _ => None,
+            })
+    }
+
+    async fn respond(
+        &self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+        content: impl ToString,
+    ) -> serenity::Result<()> {
+        interaction
+            .create_interaction_response(ctx, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| message.content(content).ephemeral(true))
+            })
+            .await
+    }
+}
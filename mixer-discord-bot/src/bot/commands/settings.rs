@@ -3,7 +3,7 @@ use serenity::builder::CreateApplicationCommand;
 use serenity::client::Context;
 use serenity::model::application::command::CommandOptionType;
 use serenity::model::application::interaction::application_command::{
-    ApplicationCommandInteraction, CommandDataOption,
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
 };
 use serenity::model::application::interaction::InteractionResponseType;
 use serenity::model::Permissions;
@@ -11,7 +11,10 @@ use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 
 use crate::bot::commands::MixerCommand;
-use entity::prelude::Role;
+use crate::database::queries::prelude::*;
+use crate::database::DatabaseContainer;
+use crate::image_manipulation::ImageGeneratorContainer;
+use entity::prelude::{ImageFormat, ProposalFormat, Role};
 
 pub struct SettingsCommand;
 
@@ -37,6 +40,72 @@ impl MixerCommand for SettingsCommand {
                             .description("Automatically assign roles based on your rank")
                     })
             })
+            .create_option(|option| {
+                option
+                    .name("host")
+                    .kind(CommandOptionType::SubCommand)
+                    .description("Set the role that can host mixes in the lobbies")
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("role")
+                            .kind(CommandOptionType::Role)
+                            .description("The mix host role, leave empty to remove it")
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("theme")
+                    .kind(CommandOptionType::SubCommand)
+                    .description("Set the theme of the mix images")
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("name")
+                            .kind(CommandOptionType::String)
+                            .description("The name of the theme, leave empty to use the default")
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("proposal")
+                    .kind(CommandOptionType::SubCommand)
+                    .description("Set how the proposed teams are shown")
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("format")
+                            .kind(CommandOptionType::String)
+                            .description("Send the teams as an image, an embed or both")
+                            .required(true)
+                            .add_string_choice("Image", "image")
+                            .add_string_choice("Embed", "embed")
+                            .add_string_choice("Both", "both")
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("image")
+                    .kind(CommandOptionType::SubCommand)
+                    .description("Set the file format of the mix images")
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("format")
+                            .kind(CommandOptionType::String)
+                            .description("WebP is lossless and smaller than PNG")
+                            .required(true)
+                            .add_string_choice("PNG", "png")
+                            .add_string_choice("WebP", "webp")
+                            .add_string_choice("JPEG", "jpeg")
+                    })
+                    .create_sub_option(|sub_option| {
+                        sub_option
+                            .name("quality")
+                            .kind(CommandOptionType::Integer)
+                            .description(
+                                "JPEG quality or PNG compression, lower is smaller (not for WebP)",
+                            )
+                            .min_int_value(1)
+                            .max_int_value(100)
+                    })
+            })
             .default_member_permissions(Permissions::ADMINISTRATOR)
             .dm_permission(false);
     }
@@ -52,6 +121,46 @@ impl MixerCommand for SettingsCommand {
                 self.process_roles_subcommand(ctx, interaction.clone(), data)
                     .await?
             }
+            "host" => {
+                self.process_host_subcommand(ctx, interaction.clone(), data)
+                    .await?
+            }
+            "theme" => {
+                if let Some(error) = self
+                    .process_theme_subcommand(ctx, interaction.clone(), data)
+                    .await?
+                {
+                    return interaction
+                        .create_interaction_response(ctx, |response| {
+                            response
+                                .kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|message| {
+                                    message.content(error).ephemeral(true)
+                                })
+                        })
+                        .await;
+                }
+            }
+            "proposal" => {
+                self.process_proposal_subcommand(ctx, interaction.clone(), data)
+                    .await?
+            }
+            "image" => {
+                if let Some(error) = self
+                    .process_image_subcommand(ctx, interaction.clone(), data)
+                    .await?
+                {
+                    return interaction
+                        .create_interaction_response(ctx, |response| {
+                            response
+                                .kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|message| {
+                                    message.content(error).ephemeral(true)
+                                })
+                        })
+                        .await;
+                }
+            }
             _ => {}
         }
 
@@ -135,4 +244,130 @@ impl SettingsCommand {
 
         Ok(())
     }
+
+    async fn process_host_subcommand(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+        data: CommandDataOption,
+    ) -> serenity::Result<()> {
+        let role = data
+            .options
+            .iter()
+            .find(|option| option.name == "role")
+            .and_then(|option| match option.resolved.as_ref() {
+                Some(CommandDataOptionValue::Role(role)) => Some(role.id),
+                _ => None,
+            });
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        GuildQuery::set_mix_host_role(db.connection(), interaction.guild_id.unwrap(), role).await;
+
+        Ok(())
+    }
+
+    async fn process_theme_subcommand(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+        data: CommandDataOption,
+    ) -> serenity::Result<Option<String>> {
+        let theme = data
+            .options
+            .iter()
+            .find(|option| option.name == "name")
+            .and_then(|option| option.value.as_ref())
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string());
+
+        let data = ctx.data.read().await;
+
+        if let Some(theme) = &theme {
+            let themes = data.get::<ImageGeneratorContainer>().unwrap().theme_names();
+            if !themes.contains(theme) {
+                return Ok(Some(if themes.is_empty() {
+                    "There are no themes installed!".to_string()
+                } else {
+                    format!("Unknown theme! Available themes: {}", themes.join(", "))
+                }));
+            }
+        }
+
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        GuildQuery::set_theme(db.connection(), interaction.guild_id.unwrap(), theme).await;
+
+        Ok(None)
+    }
+
+    async fn process_proposal_subcommand(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+        data: CommandDataOption,
+    ) -> serenity::Result<()> {
+        let format = match data
+            .options
+            .iter()
+            .find(|option| option.name == "format")
+            .and_then(|option| option.value.as_ref())
+            .and_then(|value| value.as_str())
+        {
+            Some("embed") => ProposalFormat::Embed,
+            Some("both") => ProposalFormat::Both,
+            _ => ProposalFormat::Image,
+        };
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        GuildQuery::set_proposal_format(db.connection(), interaction.guild_id.unwrap(), format)
+            .await;
+
+        Ok(())
+    }
+
+    async fn process_image_subcommand(
+        &self,
+        ctx: &Context,
+        interaction: ApplicationCommandInteraction,
+        data: CommandDataOption,
+    ) -> serenity::Result<Option<String>> {
+        let value = |name| {
+            data.options
+                .iter()
+                .find(|option| option.name == name)
+                .and_then(|option| option.value.clone())
+        };
+        let format = match value("format").as_ref().and_then(|value| value.as_str()) {
+            Some("webp") => ImageFormat::Webp,
+            Some("jpeg") => ImageFormat::Jpeg,
+            _ => ImageFormat::Png,
+        };
+        let quality = value("quality")
+            .and_then(|value| value.as_i64())
+            .map(|quality| quality as i32);
+        // only lossless webp can be encoded, there's nothing to tune
+        if format == ImageFormat::Webp && quality.is_some() {
+            return Ok(Some(
+                "WebP images are always lossless, the quality can only be set for PNG and JPEG"
+                    .to_string(),
+            ));
+        }
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        GuildQuery::set_image_format(
+            db.connection(),
+            interaction.guild_id.unwrap(),
+            format,
+            quality,
+        )
+        .await;
+
+        Ok(None)
+    }
 }
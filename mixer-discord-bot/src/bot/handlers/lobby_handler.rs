@@ -0,0 +1,491 @@
+use itertools::Itertools;
+use serenity::client::Context;
+use serenity::http::StatusCode;
+use serenity::model::channel::{
+    ChannelType, GuildChannel, PermissionOverwrite, PermissionOverwriteType,
+};
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::model::Permissions;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tracing::log::{info, warn};
+
+use crate::database::queries::prelude::*;
+use crate::database::DatabaseContainer;
+use entity::lobbies;
+use entity::prelude::LobbyStatus;
+
+pub struct LobbyHandler;
+
+// the matches of one mix run together, the lobby stays in game until the last one is reported
+#[derive(Default)]
+pub struct MatchTracker {
+    open: AtomicUsize,
+}
+
+pub struct MatchTicket<'a> {
+    open: &'a AtomicUsize,
+    ended: bool,
+}
+
+// a run of mixes holds the lobby until the last result is saved and takes it again for a next game
+pub struct Session {
+    lobby_id: i32,
+    held: AtomicBool,
+}
+
+impl Session {
+    pub async fn start(ctx: &Context, lobby_id: i32) -> Option<Self> {
+        LobbyHandler::start_session(ctx, lobby_id)
+            .await
+            .then(|| Self {
+                lobby_id,
+                held: AtomicBool::new(true),
+            })
+    }
+
+    pub async fn resume(&self, ctx: &Context) -> bool {
+        if self.held.load(Ordering::SeqCst) {
+            return true;
+        }
+
+        let resumed = LobbyHandler::start_session(ctx, self.lobby_id).await;
+        self.held.store(resumed, Ordering::SeqCst);
+        resumed
+    }
+
+    // only resets a session this run still holds, another one may have started since
+    pub async fn release(&self, ctx: &Context) {
+        if self.held.swap(false, Ordering::SeqCst) {
+            LobbyHandler::end_session(ctx, self.lobby_id).await;
+        }
+    }
+}
+
+impl MatchTracker {
+    pub fn ticket(&self) -> MatchTicket<'_> {
+        self.open.fetch_add(1, Ordering::SeqCst);
+        MatchTicket {
+            open: &self.open,
+            ended: false,
+        }
+    }
+}
+
+impl MatchTicket<'_> {
+    // the status the lobby moves to once the result of this match is in
+    pub fn report(&mut self) -> LobbyStatus {
+        if self.end() {
+            LobbyStatus::Reporting
+        } else {
+            LobbyStatus::InGame
+        }
+    }
+
+    fn end(&mut self) -> bool {
+        if self.ended {
+            return false;
+        }
+
+        self.ended = true;
+        self.open.fetch_sub(1, Ordering::SeqCst) == 1
+    }
+}
+
+// a match that is cancelled or times out doesn't hold up the others
+impl Drop for MatchTicket<'_> {
+    fn drop(&mut self) {
+        self.end();
+    }
+}
+
+impl LobbyHandler {
+    pub async fn channel_deleted(ctx: &Context, channel: &GuildChannel) {
+        let id = channel.id.0 as i64;
+        let lobby = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            for lobby in LobbyQuery::lobbies_by_guild_id(db.connection(), channel.guild_id)
+                .await
+                .unwrap_or_default()
+            {
+                if lobby.text_channel_id != Some(id) && lobby.category_id != Some(id) {
+                    continue;
+                }
+
+                let mut updated = lobby.clone();
+                if updated.text_channel_id == Some(id) {
+                    updated.text_channel_id = None;
+                }
+                if updated.category_id == Some(id) {
+                    updated.category_id = None;
+                }
+                LobbyQuery::update(db.connection(), updated).await;
+                info!(
+                    "Detached channel {} from lobby #{} because it was deleted",
+                    channel.id, lobby.id
+                );
+            }
+
+            LobbyQuery::lobby_by_channel_id(db.connection(), channel.guild_id, channel.id).await
+        };
+
+        let lobby = match lobby {
+            Some(lobby) => lobby,
+            None => return,
+        };
+
+        // a team channel is recreated, the lobby is only gone with its main channel
+        if [lobby.red_team_voice_id, lobby.blue_team_voice_id].contains(&id) {
+            let host_role = Self::host_role(ctx, channel.guild_id).await;
+            let repaired =
+                Self::recreate_team_channels(ctx, &lobby, &[id], channel.parent_id, host_role)
+                    .await;
+
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+            LobbyQuery::update(db.connection(), repaired).await;
+            return;
+        }
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        if lobby.main_voice_id == id {
+            LobbyQuery::delete(db.connection(), lobby.id).await;
+            info!(
+                "Removed lobby #{} because channel {} was deleted",
+                lobby.id, channel.id
+            );
+            return;
+        }
+
+        let match_channels = LobbyQuery::match_channels(db.connection(), lobby.id)
+            .await
+            .unwrap_or_default();
+        if let Some(channels) = match_channels
+            .iter()
+            .find(|c| c.red_team_voice_id == id || c.blue_team_voice_id == id)
+        {
+            LobbyQuery::delete_match_channels_by_channel_id(db.connection(), channel.id).await;
+            info!(
+                "Removed match {} of lobby #{} because channel {} was deleted",
+                channels.position + 1,
+                lobby.id,
+                channel.id
+            );
+
+            // match channels are always created by the bot, so the other half goes as well
+            let other = if channels.red_team_voice_id == id {
+                channels.blue_team_voice_id
+            } else {
+                channels.red_team_voice_id
+            };
+            ChannelId::from(other as u64).delete(ctx).await.ok();
+        }
+    }
+
+    pub async fn guild_left(ctx: &Context, guild_id: GuildId) {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        if LobbyQuery::delete_by_guild_id(db.connection(), guild_id).await {
+            info!("Removed the lobbies of guild {}", guild_id);
+        }
+    }
+
+    // a ready event only lists the guilds of its own shard, the rest are checked by their shards
+    pub async fn check_lobbies(ctx: &Context, guilds: &[GuildId], shard: Option<[u64; 2]>) {
+        let lobbies = {
+            let data = ctx.data.read().await;
+            let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+            LobbyQuery::lobbies(db.connection())
+                .await
+                .unwrap_or_default()
+        };
+
+        for (guild_id, lobbies) in lobbies
+            .into_iter()
+            .into_group_map_by(|lobby| GuildId::from(lobby.guild_id as u64))
+        {
+            if !Self::on_shard(guild_id, shard) {
+                continue;
+            }
+            if !guilds.contains(&guild_id) {
+                // the bot may have been removed while offline, but deleting lobbies can't be undone
+                if Self::guild_gone(ctx, guild_id).await {
+                    Self::guild_left(ctx, guild_id).await;
+                }
+                continue;
+            }
+
+            let channels = match guild_id.channels(ctx).await {
+                Ok(channels) => channels,
+                Err(why) => {
+                    warn!("Could not check the lobbies of guild {}: {}", guild_id, why);
+                    continue;
+                }
+            };
+
+            for lobby in lobbies {
+                Self::check_lobby(ctx, lobby, &channels).await;
+            }
+        }
+    }
+
+    fn on_shard(guild_id: GuildId, shard: Option<[u64; 2]>) -> bool {
+        shard.is_none_or(|[id, total]| total == 0 || (guild_id.0 >> 22) % total == id)
+    }
+
+    async fn guild_gone(ctx: &Context, guild_id: GuildId) -> bool {
+        match ctx.http.get_guild(guild_id.0).await {
+            Ok(_) => false,
+            Err(serenity::Error::Http(why)) => matches!(
+                why.status_code(),
+                Some(StatusCode::FORBIDDEN | StatusCode::NOT_FOUND)
+            ),
+            Err(why) => {
+                warn!(
+                    "Could not check if guild {} still exists: {}",
+                    guild_id, why
+                );
+                false
+            }
+        }
+    }
+
+    async fn check_lobby(
+        ctx: &Context,
+        lobby: lobbies::Model,
+        channels: &HashMap<ChannelId, GuildChannel>,
+    ) {
+        let guild_id = GuildId::from(lobby.guild_id as u64);
+        let host_role = Self::host_role(ctx, guild_id).await;
+
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        let main_channel = match channels.get(&ChannelId::from(lobby.main_voice_id as u64)) {
+            Some(channel) => channel,
+            None => {
+                LobbyQuery::delete(db.connection(), lobby.id).await;
+                warn!(
+                    "Removed lobby #{} because its main channel is missing",
+                    lobby.id
+                );
+                return;
+            }
+        };
+
+        let missing = [lobby.red_team_voice_id, lobby.blue_team_voice_id]
+            .into_iter()
+            .filter(|id| !channels.contains_key(&ChannelId::from(*id as u64)))
+            .collect_vec();
+        let mut repaired =
+            Self::recreate_team_channels(ctx, &lobby, &missing, main_channel.parent_id, host_role)
+                .await;
+
+        for (channel_id, kind) in [
+            (&mut repaired.text_channel_id, "text channel"),
+            (&mut repaired.category_id, "category"),
+        ] {
+            if let Some(id) = *channel_id {
+                if !channels.contains_key(&ChannelId::from(id as u64)) {
+                    *channel_id = None;
+                    warn!("Lobby #{} lost its {}", lobby.id, kind);
+                }
+            }
+        }
+
+        if repaired != lobby {
+            LobbyQuery::update(db.connection(), repaired).await;
+        }
+
+        for match_channels in LobbyQuery::match_channels(db.connection(), lobby.id)
+            .await
+            .unwrap_or_default()
+        {
+            let red_channel = ChannelId::from(match_channels.red_team_voice_id as u64);
+            let blue_channel = ChannelId::from(match_channels.blue_team_voice_id as u64);
+            if channels.contains_key(&red_channel) && channels.contains_key(&blue_channel) {
+                continue;
+            }
+
+            LobbyQuery::delete_match_channels_by_channel_id(db.connection(), red_channel).await;
+            red_channel.delete(ctx).await.ok();
+            blue_channel.delete(ctx).await.ok();
+            warn!(
+                "Removed match {} of lobby #{} because its channels are missing",
+                match_channels.position + 1,
+                lobby.id
+            );
+        }
+    }
+
+    async fn recreate_team_channels(
+        ctx: &Context,
+        lobby: &lobbies::Model,
+        missing: &[i64],
+        parent_id: Option<ChannelId>,
+        host_role: Option<RoleId>,
+    ) -> lobbies::Model {
+        let guild_id = GuildId::from(lobby.guild_id as u64);
+        let team_size = (lobby.tank_slots + lobby.dps_slots + lobby.support_slots) as u32;
+
+        let mut repaired = lobby.clone();
+        for (channel_id, name) in [
+            (&mut repaired.red_team_voice_id, &lobby.red_team_name),
+            (&mut repaired.blue_team_voice_id, &lobby.blue_team_name),
+        ] {
+            if !missing.contains(channel_id) {
+                continue;
+            }
+
+            let permissions = Self::permissions(
+                guild_id,
+                ctx.cache.current_user_id(),
+                host_role,
+                Permissions::CONNECT,
+            );
+            let channel = guild_id
+                .create_channel(ctx, |c| {
+                    c.name(name)
+                        .kind(ChannelType::Voice)
+                        .user_limit(team_size)
+                        .permissions(permissions);
+                    if let Some(category) = parent_id {
+                        c.category(category);
+                    }
+                    c
+                })
+                .await;
+
+            match channel {
+                Ok(channel) => {
+                    *channel_id = channel.id.0 as i64;
+                    warn!("Recreated channel \"{}\" of lobby #{}", name, lobby.id);
+                }
+                Err(why) => warn!(
+                    "Could not recreate channel \"{}\" of lobby #{}: {}",
+                    name, lobby.id, why
+                ),
+            }
+        }
+
+        repaired
+    }
+
+    pub async fn host_role(ctx: &Context, guild_id: GuildId) -> Option<RoleId> {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        GuildQuery::guild_by_guild_id(db.connection(), guild_id)
+            .await?
+            .mix_host_role_id
+            .map(|id| RoleId::from(id as u64))
+    }
+
+    pub fn permissions(
+        guild_id: GuildId,
+        bot_id: UserId,
+        host_role: Option<RoleId>,
+        everyone_deny: Permissions,
+    ) -> Vec<PermissionOverwrite> {
+        let mut permissions = vec![PermissionOverwrite {
+            allow: Permissions::VIEW_CHANNEL
+                | Permissions::CONNECT
+                | Permissions::SPEAK
+                | Permissions::MOVE_MEMBERS
+                | Permissions::MANAGE_CHANNELS
+                | Permissions::SEND_MESSAGES
+                | Permissions::EMBED_LINKS
+                | Permissions::ATTACH_FILES,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(bot_id),
+        }];
+
+        if !everyone_deny.is_empty() {
+            permissions.push(PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: everyone_deny,
+                kind: PermissionOverwriteType::Role(RoleId::from(guild_id.0)),
+            });
+        }
+
+        if let Some(host_role) = host_role {
+            permissions.push(PermissionOverwrite {
+                allow: Permissions::VIEW_CHANNEL
+                    | Permissions::CONNECT
+                    | Permissions::SPEAK
+                    | Permissions::MOVE_MEMBERS
+                    | Permissions::SEND_MESSAGES,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Role(host_role),
+            });
+        }
+
+        permissions
+    }
+
+    pub async fn start_session(ctx: &Context, lobby_id: i32) -> bool {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        LobbyQuery::transition_status(
+            db.connection(),
+            lobby_id,
+            &[LobbyStatus::Idle],
+            LobbyStatus::Proposing,
+        )
+        .await
+    }
+
+    pub async fn transition(
+        ctx: &Context,
+        lobby_id: i32,
+        from: LobbyStatus,
+        to: LobbyStatus,
+    ) -> bool {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        LobbyQuery::transition_status(db.connection(), lobby_id, &[from], to).await
+    }
+
+    pub async fn end_session(ctx: &Context, lobby_id: i32) {
+        let data = ctx.data.read().await;
+        let db = data.get::<DatabaseContainer>().unwrap().read().await;
+
+        if !LobbyQuery::set_status(db.connection(), lobby_id, LobbyStatus::Idle).await {
+            warn!("Could not reset the status of lobby {}", lobby_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lobby_stays_in_game_until_every_match_reported() {
+        let tracker = MatchTracker::default();
+        let mut first = tracker.ticket();
+        let mut second = tracker.ticket();
+
+        assert_eq!(first.report(), LobbyStatus::InGame);
+        assert_eq!(second.report(), LobbyStatus::Reporting);
+    }
+
+    #[test]
+    fn cancelled_match_does_not_hold_the_lobby() {
+        let tracker = MatchTracker::default();
+        let cancelled = tracker.ticket();
+        let mut reported = tracker.ticket();
+
+        drop(cancelled);
+
+        assert_eq!(reported.report(), LobbyStatus::Reporting);
+    }
+}
@@ -1 +1,2 @@
 pub mod command_handler;
+pub mod lobby_handler;
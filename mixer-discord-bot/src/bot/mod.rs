@@ -1,17 +1,21 @@
 pub mod commands;
-mod handlers;
+pub mod handlers;
 pub mod interactions;
 
 use serenity::async_trait;
 use serenity::client::{Context, EventHandler};
 use serenity::model::application::command::Command;
 use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::channel::GuildChannel;
 use serenity::model::gateway::Ready;
+use serenity::model::guild::{Guild, UnavailableGuild};
 use serenity::model::prelude::VoiceState;
-use tracing::log::info;
+use tracing::log::{info, warn};
 
+use crate::bot::commands::lobby::LobbyCommand;
 use crate::bot::commands::MixerCommand;
 use crate::bot::handlers::command_handler::MixerCommandHandler;
+use crate::bot::handlers::lobby_handler::LobbyHandler;
 use crate::database::queries::prelude::*;
 use crate::database::DatabaseContainer;
 use crate::CreatorContainer;
@@ -48,12 +52,30 @@ impl EventHandler for MixerBot {
                 .unwrap();
         }
 
-        Command::set_global_application_commands(ctx, |commands| {
+        Command::set_global_application_commands(&ctx, |commands| {
             self.command_handler.create_all(commands);
             commands
         })
         .await
         .unwrap();
+
+        let guilds = data_about_bot
+            .guilds
+            .iter()
+            .map(|guild| guild.id)
+            .collect::<Vec<_>>();
+        LobbyHandler::check_lobbies(&ctx, &guilds, data_about_bot.shard).await;
+    }
+
+    async fn channel_delete(&self, ctx: Context, channel: &GuildChannel) {
+        LobbyHandler::channel_deleted(&ctx, channel).await;
+    }
+
+    async fn guild_delete(&self, ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        // an unavailable guild is an outage, not the bot being removed
+        if !incomplete.unavailable {
+            LobbyHandler::guild_left(&ctx, incomplete.id).await;
+        }
     }
 
     async fn voice_state_update(&self, ctx: Context, _old: Option<VoiceState>, new: VoiceState) {
@@ -62,9 +84,8 @@ impl EventHandler for MixerBot {
                 let data = ctx.data.read().await;
                 let db = data.get::<DatabaseContainer>().unwrap().read().await;
 
-                if LobbyQuery::lobby_by_channel_id(db.connection(), guild_id, channel_id)
-                    .await
-                    .is_some()
+                if let Some(lobby) =
+                    LobbyQuery::lobby_by_channel_id(db.connection(), guild_id, channel_id).await
                 {
                     if let Some(member) = new.member {
                         if member.user.bot {
@@ -73,62 +94,59 @@ impl EventHandler for MixerBot {
                     }
 
                     PlayerQuery::create_if_not_exists(db.connection(), new.user_id).await;
+
+                    drop(db);
+                    drop(data);
+
+                    if lobby.auto_start && lobby.main_voice_id == channel_id.0 as i64 {
+                        if let Err(why) = LobbyCommand.auto_start(&ctx, lobby).await {
+                            warn!("Auto start failed: {}", why);
+                        }
+                    }
                 }
             }
         }
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        match interaction {
-            Interaction::ApplicationCommand(command) => {
-                let has_permission = {
-                    let data = ctx.data.read().await;
-                    let creator = data.get::<CreatorContainer>().unwrap().clone();
-                    let db = data.get::<DatabaseContainer>().unwrap().read().await;
-                    let guild = GuildQuery::create_if_not_exists(
-                        db.connection(),
-                        command.guild_id.unwrap(),
-                    )
-                    .await;
-
-                    let verified = match guild {
-                        Some(guild) => guild.verified,
-                        _ => false,
-                    };
-
-                    verified || command.user.id == *creator
+        // components are answered by the collectors waiting for them
+        if let Interaction::ApplicationCommand(command) = interaction {
+            let has_permission = {
+                let data = ctx.data.read().await;
+                let creator = data.get::<CreatorContainer>().unwrap().clone();
+                let db = data.get::<DatabaseContainer>().unwrap().read().await;
+                let guild =
+                    GuildQuery::create_if_not_exists(db.connection(), command.guild_id.unwrap())
+                        .await;
+
+                let verified = match guild {
+                    Some(guild) => guild.verified,
+                    _ => false,
                 };
 
-                if !has_permission {
-                    command
-                        .create_interaction_response(ctx, |response| {
-                            response
-                                .kind(InteractionResponseType::ChannelMessageWithSource)
-                                .interaction_response_data(|message| {
-                                    message
-                                        .content("You do not have permission to use this bot!")
-                                        .ephemeral(true)
-                                })
-                        })
-                        .await
-                        .unwrap();
-                    return;
-                }
+                verified || command.user.id == *creator
+            };
 
-                self.command_handler
-                    .handle_command(&ctx, command)
-                    .await
-                    .unwrap();
-            }
-            Interaction::MessageComponent(component) => {
-                component
+            if !has_permission {
+                command
                     .create_interaction_response(ctx, |response| {
-                        response.kind(InteractionResponseType::DeferredUpdateMessage)
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|message| {
+                                message
+                                    .content("You do not have permission to use this bot!")
+                                    .ephemeral(true)
+                            })
                     })
                     .await
                     .unwrap();
+                return;
             }
-            _ => {}
+
+            self.command_handler
+                .handle_command(&ctx, command)
+                .await
+                .unwrap();
         }
     }
 }
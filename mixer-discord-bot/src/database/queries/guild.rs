@@ -1,6 +1,6 @@
 use sea_orm::prelude::*;
 use sea_orm::{DatabaseConnection, IntoActiveModel, Set};
-use serenity::model::prelude::GuildId;
+use serenity::model::prelude::{GuildId, RoleId};
 
 use entity::guilds;
 use entity::prelude::*;
@@ -58,4 +58,64 @@ impl Query {
 
         Guilds::update(guild).exec(connection).await.ok()
     }
+
+    pub async fn set_mix_host_role(
+        connection: &DatabaseConnection,
+        guild_id: GuildId,
+        role_id: Option<RoleId>,
+    ) -> Option<guilds::Model> {
+        let mut guild = Query::create_if_not_exists(connection, guild_id)
+            .await?
+            .into_active_model();
+
+        guild.mix_host_role_id = Set(role_id.map(|role_id| role_id.0 as i64));
+
+        Guilds::update(guild).exec(connection).await.ok()
+    }
+
+    pub async fn set_theme(
+        connection: &DatabaseConnection,
+        guild_id: GuildId,
+        theme: Option<String>,
+    ) -> Option<guilds::Model> {
+        let mut guild = Query::create_if_not_exists(connection, guild_id)
+            .await?
+            .into_active_model();
+
+        guild.theme = Set(theme);
+
+        Guilds::update(guild).exec(connection).await.ok()
+    }
+
+    pub async fn set_proposal_format(
+        connection: &DatabaseConnection,
+        guild_id: GuildId,
+        format: ProposalFormat,
+    ) -> Option<guilds::Model> {
+        let mut guild = Query::create_if_not_exists(connection, guild_id)
+            .await?
+            .into_active_model();
+
+        guild.proposal_format = Set(format);
+
+        Guilds::update(guild).exec(connection).await.ok()
+    }
+
+    pub async fn set_image_format(
+        connection: &DatabaseConnection,
+        guild_id: GuildId,
+        format: ImageFormat,
+        quality: Option<i32>,
+    ) -> Option<guilds::Model> {
+        let mut guild = Query::create_if_not_exists(connection, guild_id)
+            .await?
+            .into_active_model();
+
+        guild.image_format = Set(format);
+        if let Some(quality) = quality {
+            guild.image_quality = Set(quality);
+        }
+
+        Guilds::update(guild).exec(connection).await.ok()
+    }
 }
@@ -1,23 +1,32 @@
 use sea_orm::prelude::*;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::NotSet;
 use sea_orm::ActiveValue::Set;
-use sea_orm::DatabaseConnection;
+use sea_orm::{Condition, DatabaseConnection, IntoActiveModel, QueryOrder};
 use serenity::model::prelude::*;
 
-use entity::lobbies;
 use entity::prelude::*;
+use entity::{lobbies, match_channels};
 
 pub struct Query;
 
 impl Query {
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         connection: &DatabaseConnection,
         guild_id: GuildId,
+        name: String,
+        category_id: Option<ChannelId>,
+        text_channel_id: Option<ChannelId>,
         main_voice_id: ChannelId,
         red_team_voice_id: ChannelId,
         blue_team_voice_id: ChannelId,
     ) -> Option<lobbies::Model> {
         let lobby = lobbies::ActiveModel {
             guild_id: Set(guild_id.0 as i64),
+            name: Set(name),
+            category_id: Set(category_id.map(|id| id.0 as i64)),
+            text_channel_id: Set(text_channel_id.map(|id| id.0 as i64)),
             main_voice_id: Set(main_voice_id.0 as i64),
             red_team_voice_id: Set(red_team_voice_id.0 as i64),
             blue_team_voice_id: Set(blue_team_voice_id.0 as i64),
@@ -29,12 +38,36 @@ impl Query {
         Self::lobby_by_channel_id(connection, guild_id, main_voice_id).await
     }
 
+    pub async fn lobby_by_id(connection: &DatabaseConnection, id: i32) -> Option<lobbies::Model> {
+        Lobbies::find_by_id(id).one(connection).await.ok()?
+    }
+
+    pub async fn lobbies(connection: &DatabaseConnection) -> Option<Vec<lobbies::Model>> {
+        Lobbies::find()
+            .order_by_asc(lobbies::Column::Id)
+            .all(connection)
+            .await
+            .ok()
+    }
+
+    pub async fn lobbies_by_guild_id(
+        connection: &DatabaseConnection,
+        guild_id: GuildId,
+    ) -> Option<Vec<lobbies::Model>> {
+        Lobbies::find()
+            .filter(lobbies::Column::GuildId.eq(guild_id.0 as i64))
+            .order_by_asc(lobbies::Column::Id)
+            .all(connection)
+            .await
+            .ok()
+    }
+
     pub async fn lobby_by_channel_id(
         connection: &DatabaseConnection,
         guild_id: GuildId,
         channel_id: ChannelId,
     ) -> Option<lobbies::Model> {
-        Lobbies::find()
+        let lobby = Lobbies::find()
             .filter(
                 lobbies::Column::GuildId.eq(guild_id.0 as i64).and(
                     lobbies::Column::MainVoiceId
@@ -45,6 +78,160 @@ impl Query {
             )
             .one(connection)
             .await
+            .ok()?;
+
+        if lobby.is_some() {
+            return lobby;
+        }
+
+        let match_channels = MatchChannels::find()
+            .filter(
+                match_channels::Column::RedTeamVoiceId
+                    .eq(channel_id.0 as i64)
+                    .or(match_channels::Column::BlueTeamVoiceId.eq(channel_id.0 as i64)),
+            )
+            .one(connection)
+            .await
+            .ok()??;
+
+        Self::lobby_by_id(connection, match_channels.lobby_id)
+            .await
+            .filter(|lobby| lobby.guild_id == guild_id.0 as i64)
+    }
+
+    pub async fn create_match_channels(
+        connection: &DatabaseConnection,
+        lobby_id: i32,
+        position: i32,
+        red_team_voice_id: ChannelId,
+        blue_team_voice_id: ChannelId,
+    ) -> Option<match_channels::Model> {
+        let channels = match_channels::ActiveModel {
+            lobby_id: Set(lobby_id),
+            position: Set(position),
+            red_team_voice_id: Set(red_team_voice_id.0 as i64),
+            blue_team_voice_id: Set(blue_team_voice_id.0 as i64),
+            ..Default::default()
+        };
+
+        MatchChannels::insert(channels)
+            .exec(connection)
+            .await
+            .ok()?;
+
+        MatchChannels::find()
+            .filter(
+                match_channels::Column::LobbyId
+                    .eq(lobby_id)
+                    .and(match_channels::Column::Position.eq(position)),
+            )
+            .one(connection)
+            .await
             .ok()?
     }
+
+    pub async fn match_channels(
+        connection: &DatabaseConnection,
+        lobby_id: i32,
+    ) -> Option<Vec<match_channels::Model>> {
+        MatchChannels::find()
+            .filter(match_channels::Column::LobbyId.eq(lobby_id))
+            .order_by_asc(match_channels::Column::Position)
+            .all(connection)
+            .await
+            .ok()
+    }
+
+    pub async fn update(
+        connection: &DatabaseConnection,
+        lobby: lobbies::Model,
+    ) -> Option<lobbies::Model> {
+        // the status is only changed through transitions
+        let mut lobby = lobby.into_active_model().reset_all();
+        lobby.status = NotSet;
+
+        Lobbies::update(lobby).exec(connection).await.ok()
+    }
+
+    pub async fn transition_status(
+        connection: &DatabaseConnection,
+        id: i32,
+        from: &[LobbyStatus],
+        to: LobbyStatus,
+    ) -> bool {
+        let condition = from.iter().fold(Condition::any(), |condition, status| {
+            condition.add(lobbies::Column::Status.eq(*status))
+        });
+
+        Lobbies::update_many()
+            .col_expr(
+                lobbies::Column::Status,
+                lobbies::Column::Status.save_as(Expr::val(to)),
+            )
+            .filter(lobbies::Column::Id.eq(id))
+            .filter(condition)
+            .exec(connection)
+            .await
+            .map(|result| result.rows_affected > 0)
+            .unwrap_or(false)
+    }
+
+    pub async fn set_status(connection: &DatabaseConnection, id: i32, status: LobbyStatus) -> bool {
+        Lobbies::update_many()
+            .col_expr(
+                lobbies::Column::Status,
+                lobbies::Column::Status.save_as(Expr::val(status)),
+            )
+            .filter(lobbies::Column::Id.eq(id))
+            .exec(connection)
+            .await
+            .map(|result| result.rows_affected > 0)
+            .unwrap_or(false)
+    }
+
+    pub async fn reset_statuses(connection: &DatabaseConnection) -> bool {
+        Lobbies::update_many()
+            .col_expr(
+                lobbies::Column::Status,
+                lobbies::Column::Status.save_as(Expr::val(LobbyStatus::Idle)),
+            )
+            .filter(lobbies::Column::Status.ne(LobbyStatus::Idle))
+            .exec(connection)
+            .await
+            .map(|result| result.rows_affected > 0)
+            .unwrap_or(false)
+    }
+
+    pub async fn delete(connection: &DatabaseConnection, id: i32) -> bool {
+        Lobbies::delete_by_id(id)
+            .exec(connection)
+            .await
+            .map(|result| result.rows_affected > 0)
+            .unwrap_or(false)
+    }
+
+    pub async fn delete_by_guild_id(connection: &DatabaseConnection, guild_id: GuildId) -> bool {
+        Lobbies::delete_many()
+            .filter(lobbies::Column::GuildId.eq(guild_id.0 as i64))
+            .exec(connection)
+            .await
+            .map(|result| result.rows_affected > 0)
+            .unwrap_or(false)
+    }
+
+    pub async fn delete_match_channels_by_channel_id(
+        connection: &DatabaseConnection,
+        channel_id: ChannelId,
+    ) -> bool {
+        MatchChannels::delete_many()
+            .filter(
+                match_channels::Column::RedTeamVoiceId
+                    .eq(channel_id.0 as i64)
+                    .or(match_channels::Column::BlueTeamVoiceId.eq(channel_id.0 as i64)),
+            )
+            .exec(connection)
+            .await
+            .map(|result| result.rows_affected > 0)
+            .unwrap_or(false)
+    }
 }
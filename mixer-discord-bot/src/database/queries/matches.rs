@@ -0,0 +1,74 @@
+use sea_orm::prelude::*;
+use sea_orm::{DatabaseConnection, IntoActiveModel, Set};
+use serenity::model::prelude::GuildId;
+
+use crate::mixer::participant::{Participant, Substitution};
+use entity::matches;
+use entity::prelude::*;
+
+pub struct Query;
+
+impl Query {
+    pub async fn create(
+        connection: &DatabaseConnection,
+        guild_id: GuildId,
+        lobby_id: i32,
+        started_at: DateTime,
+        team1: &[Participant],
+        team2: &[Participant],
+    ) -> Option<matches::Model> {
+        let game = matches::ActiveModel {
+            guild_id: Set(guild_id.0 as i64),
+            lobby_id: Set(lobby_id),
+            started_at: Set(started_at),
+            team1: Set(serde_json::to_value(team1).ok()?),
+            team2: Set(serde_json::to_value(team2).ok()?),
+            substitutions: Set(serde_json::to_value(Vec::<Substitution>::new()).ok()?),
+            ..Default::default()
+        };
+
+        let id = Matches::insert(game)
+            .exec(connection)
+            .await
+            .ok()?
+            .last_insert_id;
+
+        Self::match_by_id(connection, id).await
+    }
+
+    pub async fn match_by_id(connection: &DatabaseConnection, id: i32) -> Option<matches::Model> {
+        Matches::find_by_id(id).one(connection).await.ok()?
+    }
+
+    pub async fn add_substitution(
+        connection: &DatabaseConnection,
+        id: i32,
+        substitution: &Substitution,
+    ) -> Option<matches::Model> {
+        let game = Self::match_by_id(connection, id).await?;
+
+        let mut substitutions =
+            serde_json::from_value::<Vec<Substitution>>(game.substitutions.clone())
+                .unwrap_or_default();
+        substitutions.push(substitution.clone());
+
+        let mut game = game.into_active_model();
+        game.substitutions = Set(serde_json::to_value(substitutions).ok()?);
+
+        Matches::update(game).exec(connection).await.ok()
+    }
+
+    pub async fn finish(
+        connection: &DatabaseConnection,
+        id: i32,
+        score: Option<f32>,
+        finished_at: DateTime,
+    ) -> Option<matches::Model> {
+        let mut game = Self::match_by_id(connection, id).await?.into_active_model();
+
+        game.score = Set(score);
+        game.finished_at = Set(Some(finished_at));
+
+        Matches::update(game).exec(connection).await.ok()
+    }
+}
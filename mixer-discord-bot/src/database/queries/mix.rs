@@ -0,0 +1,48 @@
+use sea_orm::prelude::*;
+use sea_orm::{DatabaseConnection, Set};
+use serenity::model::prelude::GuildId;
+
+use crate::mixer::mixer::MixRequest;
+use entity::prelude::*;
+use entity::{mixes, players};
+
+pub struct Query;
+
+impl Query {
+    pub async fn create(
+        connection: &DatabaseConnection,
+        guild_id: GuildId,
+        lobby_id: i32,
+        request: &MixRequest,
+        players: &[players::Model],
+    ) -> Option<mixes::Model> {
+        let mix = mixes::ActiveModel {
+            guild_id: Set(guild_id.0 as i64),
+            lobby_id: Set(lobby_id),
+            created_at: Set(request.now),
+            request: Set(serde_json::to_value(request).ok()?),
+            players: Set(serde_json::to_value(players).ok()?),
+            ..Default::default()
+        };
+
+        let id = Mixes::insert(mix)
+            .exec(connection)
+            .await
+            .ok()?
+            .last_insert_id;
+
+        Mixes::find_by_id(id).one(connection).await.ok()?
+    }
+
+    pub async fn mix_by_id(
+        connection: &DatabaseConnection,
+        guild_id: GuildId,
+        id: i32,
+    ) -> Option<mixes::Model> {
+        Mixes::find_by_id(id)
+            .filter(mixes::Column::GuildId.eq(guild_id.0 as i64))
+            .one(connection)
+            .await
+            .ok()?
+    }
+}
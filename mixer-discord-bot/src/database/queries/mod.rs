@@ -1,4 +1,7 @@
 pub mod guild;
 pub mod lobby;
+pub mod matches;
+pub mod mix;
 pub mod player;
 pub mod prelude;
+pub mod queue;
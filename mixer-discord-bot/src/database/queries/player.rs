@@ -1,8 +1,9 @@
 use itertools::Itertools;
 use sea_orm::prelude::*;
-use sea_orm::{DatabaseConnection, IntoActiveModel, Set};
+use sea_orm::{DatabaseConnection, IntoActiveModel, QueryOrder, QuerySelect, Set};
 use serenity::model::prelude::UserId;
 
+use crate::mixer::player::CALIBRATED_RD;
 use crate::mixer::rating::Rating;
 use entity::players;
 use entity::prelude::*;
@@ -124,4 +125,60 @@ impl Query {
 
         Players::update(player).exec(connection).await.ok()
     }
+
+    // one page of the best calibrated players on the role, only counting those who have played
+    pub async fn top_by_role(
+        connection: &DatabaseConnection,
+        role: Role,
+        page: u64,
+        page_size: u64,
+    ) -> Option<Vec<players::Model>> {
+        let (column, rd) = match role {
+            Role::Tank => (players::Column::TankRating, players::Column::TankRd),
+            Role::Dps => (players::Column::DpsRating, players::Column::DpsRd),
+            Role::Support => (players::Column::SupportRating, players::Column::SupportRd),
+        };
+
+        Players::find()
+            .filter(players::Column::LastPlayed.is_not_null())
+            .filter(rd.lt(CALIBRATED_RD))
+            .order_by_desc(column)
+            .order_by_asc(players::Column::Id)
+            .offset(page * page_size)
+            .limit(page_size)
+            .all(connection)
+            .await
+            .ok()
+    }
+
+    pub async fn add_result(
+        connection: &DatabaseConnection,
+        user_id: UserId,
+        score: f32,
+    ) -> Option<players::Model> {
+        let model = Self::player_by_user_id(connection, user_id).await?;
+        let mut player = model.clone().into_active_model();
+
+        match score {
+            score if score > 0.5 => player.wins = Set(model.wins + 1),
+            score if score < 0.5 => player.losses = Set(model.losses + 1),
+            _ => player.draws = Set(model.draws + 1),
+        }
+
+        Players::update(player).exec(connection).await.ok()
+    }
+
+    pub async fn update_no_shows(
+        connection: &DatabaseConnection,
+        user_id: UserId,
+        no_shows: i32,
+    ) -> Option<players::Model> {
+        let mut player = Self::player_by_user_id(connection, user_id)
+            .await?
+            .into_active_model();
+
+        player.no_shows = Set(no_shows);
+
+        Players::update(player).exec(connection).await.ok()
+    }
 }
@@ -1,3 +1,6 @@
 pub use super::guild::Query as GuildQuery;
 pub use super::lobby::Query as LobbyQuery;
+pub use super::matches::Query as MatchQuery;
+pub use super::mix::Query as MixQuery;
 pub use super::player::Query as PlayerQuery;
+pub use super::queue::Query as QueueQuery;
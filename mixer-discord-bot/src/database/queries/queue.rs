@@ -0,0 +1,87 @@
+use sea_orm::prelude::*;
+use sea_orm::{DatabaseConnection, QueryOrder, Set};
+use serenity::model::prelude::UserId;
+
+use entity::prelude::*;
+use entity::queue_entries;
+
+pub struct Query;
+
+impl Query {
+    pub async fn join(
+        connection: &DatabaseConnection,
+        lobby_id: i32,
+        user_id: UserId,
+        joined_at: DateTime,
+    ) -> Option<queue_entries::Model> {
+        let entry = queue_entries::ActiveModel {
+            lobby_id: Set(lobby_id),
+            discord_id: Set(user_id.0 as i64),
+            joined_at: Set(joined_at),
+            ..Default::default()
+        };
+
+        QueueEntries::insert(entry).exec(connection).await.ok()?;
+
+        Self::entry_by_user_id(connection, lobby_id, user_id).await
+    }
+
+    pub async fn leave(connection: &DatabaseConnection, lobby_id: i32, user_id: UserId) -> bool {
+        QueueEntries::delete_many()
+            .filter(
+                queue_entries::Column::LobbyId
+                    .eq(lobby_id)
+                    .and(queue_entries::Column::DiscordId.eq(user_id.0 as i64)),
+            )
+            .exec(connection)
+            .await
+            .map(|result| result.rows_affected > 0)
+            .unwrap_or(false)
+    }
+
+    pub async fn remove(
+        connection: &DatabaseConnection,
+        lobby_id: i32,
+        user_ids: &[UserId],
+    ) -> u64 {
+        QueueEntries::delete_many()
+            .filter(
+                queue_entries::Column::LobbyId.eq(lobby_id).and(
+                    queue_entries::Column::DiscordId
+                        .is_in(user_ids.iter().map(|user_id| user_id.0 as i64)),
+                ),
+            )
+            .exec(connection)
+            .await
+            .map(|result| result.rows_affected)
+            .unwrap_or(0)
+    }
+
+    pub async fn entry_by_user_id(
+        connection: &DatabaseConnection,
+        lobby_id: i32,
+        user_id: UserId,
+    ) -> Option<queue_entries::Model> {
+        QueueEntries::find()
+            .filter(
+                queue_entries::Column::LobbyId
+                    .eq(lobby_id)
+                    .and(queue_entries::Column::DiscordId.eq(user_id.0 as i64)),
+            )
+            .one(connection)
+            .await
+            .ok()?
+    }
+
+    pub async fn entries(
+        connection: &DatabaseConnection,
+        lobby_id: i32,
+    ) -> Option<Vec<queue_entries::Model>> {
+        QueueEntries::find()
+            .filter(queue_entries::Column::LobbyId.eq(lobby_id))
+            .order_by_asc(queue_entries::Column::JoinedAt)
+            .all(connection)
+            .await
+            .ok()
+    }
+}
@@ -0,0 +1,231 @@
+use image::imageops::FilterType;
+use image::RgbaImage;
+use lru::LruCache;
+use serenity::async_trait;
+use serenity::model::user::User;
+use serenity::prelude::TypeMapKey;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::log::warn;
+
+pub const AVATAR_SIZE: u32 = 54;
+pub const AVATAR_CACHE_SIZE: usize = 256;
+// avatars are fetched before the teams are announced, a stalled request must not hold that up
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[async_trait]
+pub trait AvatarSource: Send + Sync {
+    async fn fetch(&self, url: &str) -> Option<Vec<u8>>;
+}
+
+pub struct HttpAvatarSource {
+    client: reqwest::Client,
+}
+
+pub struct AvatarCache {
+    source: Box<dyn AvatarSource>,
+    avatars: Mutex<LruCache<String, RgbaImage>>,
+}
+
+impl HttpAvatarSource {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(FETCH_TIMEOUT)
+                .build()
+                .unwrap(),
+        }
+    }
+}
+
+#[async_trait]
+impl AvatarSource for HttpAvatarSource {
+    async fn fetch(&self, url: &str) -> Option<Vec<u8>> {
+        let response = match self.client.get(url).send().await {
+            Ok(response) => response,
+            Err(why) => {
+                warn!("Could not fetch avatar {}: {}", url, why);
+                return None;
+            }
+        };
+        if !response.status().is_success() {
+            warn!("Could not fetch avatar {}: {}", url, response.status());
+            return None;
+        }
+
+        response.bytes().await.ok().map(|bytes| bytes.to_vec())
+    }
+}
+
+impl AvatarCache {
+    pub fn new(source: Box<dyn AvatarSource>, capacity: usize) -> Self {
+        Self {
+            source,
+            avatars: Mutex::new(LruCache::new(
+                NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN),
+            )),
+        }
+    }
+
+    // the url contains the avatar hash, so a changed avatar is a cache miss
+    pub async fn avatar(&self, user: &User) -> Option<RgbaImage> {
+        let url = avatar_url(user);
+        if let Some(avatar) = self.load(&url).await {
+            return Some(avatar);
+        }
+
+        // a custom avatar that can't be fetched is replaced with the default one
+        let default_url = user.default_avatar_url();
+        if url == default_url {
+            return None;
+        }
+        self.load(&default_url).await
+    }
+
+    async fn load(&self, url: &str) -> Option<RgbaImage> {
+        if let Some(avatar) = self.avatars.lock().await.get(url) {
+            return Some(avatar.clone());
+        }
+
+        let bytes = self.source.fetch(url).await?;
+        let avatar = image::load_from_memory(&bytes).ok()?.to_rgba8();
+        let avatar =
+            image::imageops::resize(&avatar, AVATAR_SIZE, AVATAR_SIZE, FilterType::Triangle);
+
+        self.avatars
+            .lock()
+            .await
+            .put(url.to_string(), avatar.clone());
+
+        Some(avatar)
+    }
+}
+
+fn avatar_url(user: &User) -> String {
+    match &user.avatar {
+        Some(hash) => format!(
+            "https://cdn.discordapp.com/avatars/{}/{}.png?size=64",
+            user.id, hash
+        ),
+        None => user.default_avatar_url(),
+    }
+}
+
+pub struct AvatarCacheContainer;
+
+impl TypeMapKey for AvatarCacheContainer {
+    type Value = Arc<AvatarCache>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageOutputFormat, Rgba};
+    use std::collections::HashMap;
+    use std::io::Cursor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex as StdMutex;
+
+    #[derive(Default)]
+    struct StubSource {
+        avatars: HashMap<String, Vec<u8>>,
+        fetched: Arc<StdMutex<Vec<String>>>,
+        fetches: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl AvatarSource for StubSource {
+        async fn fetch(&self, url: &str) -> Option<Vec<u8>> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            self.fetched.lock().unwrap().push(url.to_string());
+            self.avatars.get(url).cloned()
+        }
+    }
+
+    fn png(colour: [u8; 4]) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(64, 64, Rgba(colour));
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageOutputFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn user(id: u64, avatar: Option<&str>) -> User {
+        let mut user = User::default();
+        user.id = id.into();
+        user.avatar = avatar.map(str::to_string);
+        user
+    }
+
+    #[tokio::test]
+    async fn cached_avatar_is_not_fetched_again() {
+        let alice = user(1, Some("a"));
+        let source = StubSource {
+            avatars: HashMap::from([(avatar_url(&alice), png([255, 0, 0, 255]))]),
+            ..Default::default()
+        };
+        let fetches = source.fetches.clone();
+        let cache = AvatarCache::new(Box::new(source), 2);
+
+        let first = cache.avatar(&alice).await.unwrap();
+        let second = cache.avatar(&alice).await.unwrap();
+
+        assert_eq!(first.dimensions(), (AVATAR_SIZE, AVATAR_SIZE));
+        assert_eq!(first, second);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_avatar_is_evicted() {
+        let users = [user(1, Some("a")), user(2, Some("b")), user(3, Some("c"))];
+        let source = StubSource {
+            avatars: users
+                .iter()
+                .map(|user| (avatar_url(user), png([0, 255, 0, 255])))
+                .collect(),
+            ..Default::default()
+        };
+        let fetched = source.fetched.clone();
+        let cache = AvatarCache::new(Box::new(source), 2);
+
+        cache.avatar(&users[0]).await.unwrap();
+        cache.avatar(&users[1]).await.unwrap();
+        // touch the first avatar, so the second one is the least recently used
+        cache.avatar(&users[0]).await.unwrap();
+        cache.avatar(&users[2]).await.unwrap();
+        fetched.lock().unwrap().clear();
+
+        cache.avatar(&users[0]).await.unwrap();
+        cache.avatar(&users[1]).await.unwrap();
+
+        assert_eq!(*fetched.lock().unwrap(), vec![avatar_url(&users[1])]);
+    }
+
+    #[tokio::test]
+    async fn failed_fetch_falls_back_to_default_avatar() {
+        let alice = user(1, Some("a"));
+        let default = png([0, 0, 255, 255]);
+        let source = StubSource {
+            avatars: HashMap::from([(alice.default_avatar_url(), default.clone())]),
+            ..Default::default()
+        };
+        let fetched = source.fetched.clone();
+        let cache = AvatarCache::new(Box::new(source), 2);
+
+        let avatar = cache.avatar(&alice).await.unwrap();
+
+        assert_eq!(avatar.get_pixel(0, 0), &Rgba([0, 0, 255, 255]));
+        assert_eq!(
+            *fetched.lock().unwrap(),
+            vec![avatar_url(&alice), alice.default_avatar_url()]
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_default_avatar_is_none() {
+        let cache = AvatarCache::new(Box::<StubSource>::default(), 2);
+
+        assert!(cache.avatar(&user(1, None)).await.is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::{ColorType, ImageEncoder, ImageError, RgbImage};
+use serenity::model::prelude::AttachmentType;
+use std::borrow::Cow;
+use tracing::log::warn;
+
+use crate::image_manipulation::RenderError;
+use entity::guilds;
+use entity::prelude::ImageFormat;
+
+// the upload limit of servers without boosts
+pub const MAX_ATTACHMENT_SIZE: usize = 8 * 1024 * 1024;
+// jpeg qualities tried in order when an image is over the limit
+const SHRINK_QUALITIES: [u8; 3] = [80, 60, 40];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    // lower qualities compress harder, the image itself is the same
+    Png(u8),
+    Jpeg(u8),
+    // lossless, lossy webp needs libwebp
+    WebP,
+}
+
+pub struct EncodedImage {
+    pub data: Vec<u8>,
+    pub extension: &'static str,
+    // tells the guild that its format was replaced to fit the upload limit
+    pub notice: Option<String>,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png(_) => "png",
+            OutputFormat::Jpeg(_) => "jpg",
+            OutputFormat::WebP => "webp",
+        }
+    }
+}
+
+impl From<&guilds::Model> for OutputFormat {
+    fn from(guild: &guilds::Model) -> Self {
+        let quality = guild.image_quality.clamp(1, 100) as u8;
+
+        match guild.image_format {
+            ImageFormat::Png => OutputFormat::Png(quality),
+            ImageFormat::Jpeg => OutputFormat::Jpeg(quality),
+            ImageFormat::Webp => OutputFormat::WebP,
+        }
+    }
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Png(90)
+    }
+}
+
+impl EncodedImage {
+    pub fn into_attachment(self, name: &str) -> AttachmentType<'static> {
+        AttachmentType::Bytes {
+            filename: format!("{}.{}", name, self.extension),
+            data: Cow::Owned(self.data),
+        }
+    }
+}
+
+pub fn encode(image: &RgbImage, format: OutputFormat) -> Result<EncodedImage, RenderError> {
+    let data = write(image, format)?;
+    if data.len() <= MAX_ATTACHMENT_SIZE {
+        return Ok(EncodedImage {
+            data,
+            extension: format.extension(),
+            notice: None,
+        });
+    }
+
+    // discord rejects the upload, so trade quality for size instead
+    for quality in SHRINK_QUALITIES {
+        let shrunk = write(image, OutputFormat::Jpeg(quality))?;
+        if shrunk.len() <= MAX_ATTACHMENT_SIZE {
+            warn!(
+                "Image was {} bytes as {:?}, sending it as jpeg with quality {}",
+                data.len(),
+                format,
+                quality
+            );
+            return Ok(EncodedImage {
+                data: shrunk,
+                extension: OutputFormat::Jpeg(quality).extension(),
+                notice: Some(format!(
+                    "The image was too large to upload as {}, so it was sent as a JPEG instead. \
+                    A smaller format can be set with `/settings image`.",
+                    format.extension().to_uppercase()
+                )),
+            });
+        }
+    }
+
+    Err(RenderError::TooLarge(data.len()))
+}
+
+fn write(image: &RgbImage, format: OutputFormat) -> Result<Vec<u8>, ImageError> {
+    let mut buf = Vec::new();
+    let (width, height) = image.dimensions();
+
+    match format {
+        OutputFormat::Png(quality) => {
+            let compression = match quality {
+                95.. => CompressionType::Fast,
+                50..=94 => CompressionType::Default,
+                _ => CompressionType::Best,
+            };
+            PngEncoder::new_with_quality(&mut buf, compression, FilterType::Adaptive).write_image(
+                image.as_raw(),
+                width,
+                height,
+                ColorType::Rgb8,
+            )?;
+        }
+        OutputFormat::Jpeg(quality) => JpegEncoder::new_with_quality(&mut buf, quality)
+            .write_image(image.as_raw(), width, height, ColorType::Rgb8)?,
+        OutputFormat::WebP => WebPEncoder::new_lossless(&mut buf).write_image(
+            image.as_raw(),
+            width,
+            height,
+            ColorType::Rgb8,
+        )?,
+    }
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // noise doesn't compress, so a lossless encoding is about as big as the raw pixels
+    fn noise(width: u32, height: u32) -> RgbImage {
+        let mut state = 0x2545_f491_u32;
+        RgbImage::from_fn(width, height, |_, _| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let [r, g, b, _] = state.to_le_bytes();
+            image::Rgb([r, g, b])
+        })
+    }
+
+    #[test]
+    fn small_image_keeps_its_format() {
+        let image = encode(&noise(64, 64), OutputFormat::Png(90)).unwrap();
+
+        assert_eq!(image.extension, "png");
+        assert!(image.notice.is_none());
+    }
+
+    #[test]
+    fn oversized_image_falls_back_to_jpeg_with_a_notice() {
+        let image = encode(&noise(1800, 1800), OutputFormat::Png(90)).unwrap();
+
+        assert_eq!(image.extension, "jpg");
+        assert!(image.data.len() <= MAX_ATTACHMENT_SIZE);
+        assert!(image.notice.unwrap().contains("PNG"));
+    }
+}
@@ -0,0 +1,1082 @@
+use image::{ImageError, Rgb, RgbImage, RgbaImage};
+use imageproc::drawing::{draw_filled_circle_mut, draw_filled_rect_mut, draw_polygon_mut};
+use imageproc::point::Point;
+use imageproc::rect::Rect;
+use rusttype::{Font, Scale};
+use serenity::prelude::TypeMapKey;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use crate::image_manipulation::avatar::AVATAR_SIZE;
+use crate::image_manipulation::encoding::{
+    encode, EncodedImage, OutputFormat, MAX_ATTACHMENT_SIZE,
+};
+use crate::image_manipulation::text::{draw_runs, strip_emoji, text_width};
+use crate::image_manipulation::theme::{Align, TextStyle, Theme};
+use entity::prelude::Role;
+
+pub mod avatar;
+pub mod encoding;
+pub mod text;
+pub mod theme;
+
+const MARGIN: i32 = 16;
+const HEADER_HEIGHT: i32 = 100;
+const ROW_HEIGHT: i32 = 70;
+const COLUMN_WIDTH: i32 = 428;
+const COLUMN_GAP: i32 = 112;
+const ROLE_WIDTH: i32 = 56;
+const RATING_WIDTH: i32 = 156;
+const PLAYER_RATING_WIDTH: i32 = 130;
+const RESULT_COLUMN_WIDTH: i32 = 560;
+const CHANGE_RATING_WIDTH: i32 = 64;
+const ARROW_WIDTH: i32 = 28;
+const DELTA_WIDTH: i32 = 56;
+const LEADERBOARD_COLUMN_WIDTH: i32 = 400;
+const LEADERBOARD_RATING_WIDTH: i32 = 80;
+const PROFILE_COLUMN_WIDTH: i32 = 640;
+const PREFERENCE_WIDTH: i32 = 48;
+const BAR_HEIGHT: i32 = 20;
+const MAX_BAR_RATING: f32 = 5000.0;
+const RATING_CHANGE_WIDTH: i32 = 2 * CHANGE_RATING_WIDTH + ARROW_WIDTH + DELTA_WIDTH + PADDING;
+const FOOTER_HEIGHT: i32 = 60;
+const PADDING: i32 = 12;
+
+// renders running on the blocking pool at the same time
+const MAX_RENDERS: usize = 2;
+
+pub struct ImageGenerator<'a> {
+    pub default_theme: Theme<'a>,
+    pub themes: HashMap<String, Theme<'a>>,
+    // tried in order for characters missing from the theme fonts
+    pub fallback_fonts: Vec<Font<'a>>,
+    renders: Semaphore,
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    Encode(ImageError),
+    TooLarge(usize),
+    Panicked,
+    Closed,
+}
+
+pub struct TeamImage {
+    pub name: String,
+    pub rating: i32,
+    pub slots: Vec<SlotImage>,
+}
+
+pub struct SlotImage {
+    pub role: Role,
+    pub name: Option<String>,
+    pub rating: Option<i32>,
+    pub rd: Option<i32>,
+    pub avatar: Option<RgbaImage>,
+}
+
+pub struct ResultImage {
+    pub teams: Vec<ResultTeamImage>,
+    pub winner: Option<usize>,
+    pub duration: Duration,
+}
+
+pub struct ResultTeamImage {
+    pub name: String,
+    pub players: Vec<ResultPlayerImage>,
+}
+
+pub struct ResultPlayerImage {
+    pub role: Role,
+    pub name: String,
+    pub avatar: Option<RgbaImage>,
+    pub rating: i32,
+    pub new_rating: Option<i32>,
+}
+
+pub struct LeaderboardColumn {
+    pub role: Role,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub avatar: Option<RgbaImage>,
+    pub rating: i32,
+}
+
+pub struct ProfileImage {
+    pub name: String,
+    pub avatar: Option<RgbaImage>,
+    pub roles: Vec<ProfileRole>,
+    pub wins: i32,
+    pub losses: i32,
+    pub draws: i32,
+    pub flex: bool,
+}
+
+pub struct ProfileRole {
+    pub role: Role,
+    pub rating: i32,
+    pub rd: i32,
+    // position in the player's role preference
+    pub preference: Option<usize>,
+}
+
+pub struct ColumnLayout {
+    pub name: Rect,
+    pub rating: Rect,
+    pub panel: Rect,
+    pub rows: Vec<Rect>,
+}
+
+pub struct TeamsLayout {
+    pub width: u32,
+    pub height: u32,
+    pub columns: Vec<ColumnLayout>,
+    pub gaps: Vec<Rect>,
+    pub footer: Option<Rect>,
+}
+
+impl TeamsLayout {
+    pub fn new(
+        teams: usize,
+        rows: usize,
+        column_width: i32,
+        column_gap: i32,
+        footer: bool,
+    ) -> Self {
+        let teams = teams.max(1) as i32;
+        let rows = rows.max(1) as i32;
+        let panel_y = MARGIN + HEADER_HEIGHT;
+        let panel_height = rows * ROW_HEIGHT;
+
+        let columns = (0..teams)
+            .map(|i| {
+                let x = MARGIN + i * (column_width + column_gap);
+
+                ColumnLayout {
+                    name: Rect::at(x, MARGIN).of_size(
+                        (column_width - RATING_WIDTH - MARGIN) as u32,
+                        (HEADER_HEIGHT - MARGIN) as u32,
+                    ),
+                    rating: Rect::at(x + column_width - RATING_WIDTH, MARGIN)
+                        .of_size(RATING_WIDTH as u32, (HEADER_HEIGHT - MARGIN) as u32),
+                    panel: Rect::at(x, panel_y).of_size(column_width as u32, panel_height as u32),
+                    rows: (0..rows)
+                        .map(|j| {
+                            Rect::at(x, panel_y + j * ROW_HEIGHT)
+                                .of_size(column_width as u32, ROW_HEIGHT as u32)
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let gaps = (1..teams)
+            .map(|i| {
+                Rect::at(
+                    i * (column_width + column_gap) - column_gap + MARGIN,
+                    panel_y,
+                )
+                .of_size(column_gap as u32, panel_height as u32)
+            })
+            .collect();
+
+        let width = 2 * MARGIN + teams * column_width + (teams - 1) * column_gap;
+        let footer = footer.then(|| {
+            Rect::at(MARGIN, panel_y + panel_height + MARGIN)
+                .of_size((width - 2 * MARGIN) as u32, FOOTER_HEIGHT as u32)
+        });
+        let footer_height = footer.map_or(0, |_| FOOTER_HEIGHT + MARGIN);
+
+        Self {
+            width: width as u32,
+            height: (2 * MARGIN + HEADER_HEIGHT + panel_height + footer_height) as u32,
+            columns,
+            gaps,
+            footer,
+        }
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Encode(why) => write!(f, "could not encode the image: {}", why),
+            RenderError::TooLarge(size) => write!(
+                f,
+                "the image is {} bytes, over the attachment limit of {}",
+                size, MAX_ATTACHMENT_SIZE
+            ),
+            RenderError::Panicked => write!(f, "the render task panicked"),
+            RenderError::Closed => write!(f, "the renderer is shutting down"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<ImageError> for RenderError {
+    fn from(why: ImageError) -> Self {
+        RenderError::Encode(why)
+    }
+}
+
+impl ImageGenerator<'static> {
+    // drawing and encoding are too slow for the async workers, so they run on the blocking pool
+    pub async fn render<F>(self: Arc<Self>, draw: F) -> Result<EncodedImage, RenderError>
+    where
+        F: FnOnce(&ImageGenerator<'static>) -> Result<EncodedImage, RenderError> + Send + 'static,
+    {
+        let _permit = self
+            .renders
+            .acquire()
+            .await
+            .map_err(|_| RenderError::Closed)?;
+
+        let generator = self.clone();
+        tokio::task::spawn_blocking(move || draw(&generator))
+            .await
+            .map_err(|_| RenderError::Panicked)?
+    }
+}
+
+impl<'a> ImageGenerator<'a> {
+    pub fn new(
+        default_theme: Theme<'a>,
+        themes: HashMap<String, Theme<'a>>,
+        fallback_fonts: Vec<Font<'a>>,
+    ) -> Self {
+        Self {
+            default_theme,
+            themes,
+            fallback_fonts,
+            renders: Semaphore::new(MAX_RENDERS),
+        }
+    }
+
+    // unknown themes fall back to the built-in one
+    pub fn theme(&self, name: Option<&str>) -> &Theme<'a> {
+        name.and_then(|name| self.themes.get(name))
+            .unwrap_or(&self.default_theme)
+    }
+
+    pub fn theme_names(&self) -> Vec<String> {
+        let mut names = self.themes.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    pub fn draw_teams_to_vec(
+        &self,
+        theme: Option<&str>,
+        teams: &[TeamImage],
+        role_gaps: &[(Role, i32)],
+        format: OutputFormat,
+    ) -> Result<EncodedImage, RenderError> {
+        let rows = teams.iter().map(|team| team.slots.len()).max().unwrap_or(0);
+        let layout = TeamsLayout::new(
+            teams.len(),
+            rows,
+            COLUMN_WIDTH,
+            COLUMN_GAP,
+            !role_gaps.is_empty(),
+        );
+
+        let theme = self.theme(theme);
+        let text_color = Rgb(theme.colors.text);
+        let mut image = canvas(theme, &layout);
+
+        for (i, (team, column)) in teams.iter().zip(&layout.columns).enumerate() {
+            let team_color = theme.colors.team(i);
+            self.draw_column(
+                &mut image,
+                theme,
+                column,
+                team_color,
+                &team.name,
+                &team.rating.to_string(),
+                Rgb(theme.colors.rating_background),
+            );
+
+            for (row, slot) in column.rows.iter().zip(&team.slots) {
+                self.draw_slot(
+                    &mut image,
+                    theme,
+                    *row,
+                    team_color,
+                    &slot.role,
+                    slot.name.as_deref(),
+                    slot.avatar.as_ref(),
+                    PLAYER_RATING_WIDTH,
+                );
+
+                let rating = match (slot.rating, slot.rd) {
+                    (Some(rating), Some(rd)) => format!("{} ({})", rating, rd),
+                    (Some(rating), None) => rating.to_string(),
+                    _ => String::new(),
+                };
+                let rating_rect = Rect::at(row.right() - PADDING - PLAYER_RATING_WIDTH, row.top())
+                    .of_size(PLAYER_RATING_WIDTH as u32, row.height());
+                self.draw_text(
+                    &mut image,
+                    rating_rect,
+                    &rating,
+                    &theme.text_font,
+                    theme.text.player_rating,
+                    text_color,
+                );
+            }
+        }
+
+        self.draw_gaps(&mut image, theme, &layout);
+
+        if let Some(footer) = layout.footer {
+            let cell_width = footer.width() / role_gaps.len() as u32;
+            for (i, (role, gap)) in role_gaps.iter().enumerate() {
+                let cell = Rect::at(footer.left() + (cell_width * i as u32) as i32, footer.top())
+                    .of_size(cell_width, footer.height());
+                let color = match gap {
+                    gap if *gap > 0 => theme.colors.team(0),
+                    gap if *gap < 0 => theme.colors.team(1),
+                    _ => text_color,
+                };
+
+                let icon =
+                    Rect::at(cell.left(), cell.top()).of_size(ROLE_WIDTH as u32, cell.height());
+                draw_role_icon(&mut image, icon, role, color);
+                self.draw_text(
+                    &mut image,
+                    Rect::at(cell.left() + ROLE_WIDTH, cell.top())
+                        .of_size(cell.width() - ROLE_WIDTH as u32, cell.height()),
+                    &format!("{:+}", gap),
+                    &theme.text_font,
+                    theme.text.role_gap,
+                    color,
+                );
+            }
+        }
+
+        encode(&image, format)
+    }
+
+    pub fn draw_result_to_vec(
+        &self,
+        theme: Option<&str>,
+        result: &ResultImage,
+        format: OutputFormat,
+    ) -> Result<EncodedImage, RenderError> {
+        let rows = result
+            .teams
+            .iter()
+            .map(|team| team.players.len())
+            .max()
+            .unwrap_or(0);
+        let layout = TeamsLayout::new(
+            result.teams.len(),
+            rows,
+            RESULT_COLUMN_WIDTH,
+            COLUMN_GAP,
+            true,
+        );
+
+        let theme = self.theme(theme);
+        let text_color = Rgb(theme.colors.text);
+        let mut image = canvas(theme, &layout);
+
+        for (i, (team, column)) in result.teams.iter().zip(&layout.columns).enumerate() {
+            let team_color = theme.colors.team(i);
+            let (outcome, outcome_color) = match result.winner {
+                Some(winner) if winner == i => ("WIN", theme.colors.positive),
+                Some(_) => ("LOSS", theme.colors.negative),
+                None => ("DRAW", theme.colors.rating_background),
+            };
+            self.draw_column(
+                &mut image,
+                theme,
+                column,
+                team_color,
+                &team.name,
+                outcome,
+                Rgb(outcome_color),
+            );
+
+            for (row, player) in column.rows.iter().zip(&team.players) {
+                self.draw_slot(
+                    &mut image,
+                    theme,
+                    *row,
+                    team_color,
+                    &player.role,
+                    Some(&player.name),
+                    player.avatar.as_ref(),
+                    RATING_CHANGE_WIDTH,
+                );
+                self.draw_rating_change(&mut image, theme, *row, player);
+            }
+        }
+
+        self.draw_gaps(&mut image, theme, &layout);
+
+        if let Some(footer) = layout.footer {
+            let (headline, color) = match result.winner {
+                Some(winner) => (
+                    format!("{} wins", result.teams[winner].name),
+                    theme.colors.team(winner),
+                ),
+                None => ("Draw".to_string(), text_color),
+            };
+            self.draw_text(
+                &mut image,
+                footer,
+                &headline.to_uppercase(),
+                &theme.text_font,
+                theme.text.title,
+                color,
+            );
+
+            let seconds = result.duration.as_secs();
+            let duration = match seconds / 3600 {
+                0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+                hours => format!("{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+            };
+            self.draw_text(
+                &mut image,
+                footer,
+                &duration,
+                &theme.text_font,
+                TextStyle {
+                    align: Align::Right,
+                    ..theme.text.title
+                },
+                text_color,
+            );
+        }
+
+        encode(&image, format)
+    }
+
+    pub fn draw_leaderboard_to_vec(
+        &self,
+        theme: Option<&str>,
+        columns: &[LeaderboardColumn],
+        format: OutputFormat,
+    ) -> Result<EncodedImage, RenderError> {
+        let rows = columns
+            .iter()
+            .map(|column| column.entries.len())
+            .max()
+            .unwrap_or(0);
+        let layout = TeamsLayout::new(columns.len(), rows, LEADERBOARD_COLUMN_WIDTH, MARGIN, false);
+
+        let theme = self.theme(theme);
+        let text_color = Rgb(theme.colors.text);
+        let panel_color = Rgb(theme.colors.rating_background);
+        let mut image = canvas(theme, &layout);
+
+        for (column, column_layout) in columns.iter().zip(&layout.columns) {
+            // the role takes the whole header, there is no badge
+            let header = column_layout.name;
+            let icon =
+                Rect::at(header.left(), header.top()).of_size(header.height(), header.height());
+            draw_role_icon(&mut image, icon, &column.role, text_color);
+            self.draw_text(
+                &mut image,
+                Rect::at(icon.right() + PADDING, header.top()).of_size(
+                    column_layout.panel.width() - icon.width() - PADDING as u32,
+                    header.height(),
+                ),
+                &column.role.to_string().to_uppercase(),
+                &theme.text_font,
+                theme.text.team_name,
+                text_color,
+            );
+
+            fill_blended(
+                &mut image,
+                column_layout.panel,
+                panel_color,
+                theme.colors.panel_opacity,
+            );
+
+            for (i, (row, entry)) in column_layout.rows.iter().zip(&column.entries).enumerate() {
+                let rank_rect =
+                    Rect::at(row.left(), row.top()).of_size(ROLE_WIDTH as u32, row.height());
+                let (badge, badge_text) = match theme.colors.ranks.get(i) {
+                    Some(color) => (Rgb(*color), Rgb(theme.colors.background)),
+                    None => (panel_color, text_color),
+                };
+                draw_filled_rect_mut(&mut image, rank_rect, badge);
+                self.draw_text(
+                    &mut image,
+                    rank_rect,
+                    &(i + 1).to_string(),
+                    &theme.text_font,
+                    TextStyle {
+                        align: Align::Center,
+                        ..theme.text.player_rating
+                    },
+                    badge_text,
+                );
+
+                self.draw_player(
+                    &mut image,
+                    theme,
+                    *row,
+                    panel_color,
+                    Some(&entry.name),
+                    entry.avatar.as_ref(),
+                    LEADERBOARD_RATING_WIDTH,
+                );
+                self.draw_text(
+                    &mut image,
+                    Rect::at(row.right() - PADDING - LEADERBOARD_RATING_WIDTH, row.top())
+                        .of_size(LEADERBOARD_RATING_WIDTH as u32, row.height()),
+                    &entry.rating.to_string(),
+                    &theme.text_font,
+                    theme.text.player_rating,
+                    text_color,
+                );
+            }
+        }
+
+        encode(&image, format)
+    }
+
+    pub fn draw_profile_to_vec(
+        &self,
+        theme: Option<&str>,
+        profile: &ProfileImage,
+        format: OutputFormat,
+    ) -> Result<EncodedImage, RenderError> {
+        let layout = TeamsLayout::new(
+            1,
+            profile.roles.len(),
+            PROFILE_COLUMN_WIDTH,
+            COLUMN_GAP,
+            true,
+        );
+        let column = &layout.columns[0];
+
+        let theme = self.theme(theme);
+        let text_color = Rgb(theme.colors.text);
+        let panel_color = Rgb(theme.colors.rating_background);
+        let bar_color = Rgb(theme.colors.bar);
+        let mut image = canvas(theme, &layout);
+
+        let header = column.name;
+        let avatar_y = header.top() + (header.height() as i32 - AVATAR_SIZE as i32) / 2;
+        match &profile.avatar {
+            Some(avatar) => draw_avatar(&mut image, avatar, header.left(), avatar_y),
+            None => self.draw_avatar_placeholder(
+                &mut image,
+                header.left(),
+                avatar_y,
+                &profile.name,
+                theme,
+                panel_color,
+            ),
+        }
+        let name_x = header.left() + AVATAR_SIZE as i32 + PADDING;
+        self.draw_text(
+            &mut image,
+            Rect::at(name_x, header.top())
+                .of_size((header.right() - name_x) as u32, header.height()),
+            &profile.name,
+            &theme.player_font,
+            theme.text.team_name,
+            text_color,
+        );
+
+        let games = profile.wins + profile.losses + profile.draws;
+        let (win_rate, win_rate_color) = match games {
+            0 => ("-".to_string(), panel_color),
+            _ => {
+                let win_rate = profile.wins * 100 / games;
+                let color = match win_rate {
+                    win_rate if win_rate >= 50 => theme.colors.positive,
+                    _ => theme.colors.negative,
+                };
+                (format!("{}%", win_rate), Rgb(color))
+            }
+        };
+        draw_filled_rect_mut(&mut image, column.rating, win_rate_color);
+        self.draw_text(
+            &mut image,
+            column.rating,
+            &win_rate,
+            &theme.text_font,
+            theme.text.rating,
+            text_color,
+        );
+
+        fill_blended(
+            &mut image,
+            column.panel,
+            panel_color,
+            theme.colors.panel_opacity,
+        );
+
+        for (row, role) in column.rows.iter().zip(&profile.roles) {
+            let strip = Rect::at(row.left(), row.top()).of_size(ROLE_WIDTH as u32, row.height());
+            let strip_color = match role.preference {
+                Some(_) => bar_color,
+                None => panel_color,
+            };
+            draw_filled_rect_mut(&mut image, strip, strip_color);
+            draw_role_icon(&mut image, strip, &role.role, text_color);
+
+            let preference = match role.preference {
+                Some(0) => "1ST",
+                Some(1) => "2ND",
+                Some(2) => "3RD",
+                _ => "",
+            };
+            let preference_rect = Rect::at(strip.right() + PADDING, row.top())
+                .of_size(PREFERENCE_WIDTH as u32, row.height());
+            self.draw_text(
+                &mut image,
+                preference_rect,
+                preference,
+                &theme.text_font,
+                theme.text.player_rating,
+                text_color,
+            );
+
+            let rating_x = row.right() - PADDING - PLAYER_RATING_WIDTH;
+            let bar_x = preference_rect.right() + PADDING;
+            let bar_width = rating_x - PADDING - bar_x;
+            let bar_y = row.top() + (row.height() as i32 - BAR_HEIGHT) / 2;
+            draw_filled_rect_mut(
+                &mut image,
+                Rect::at(bar_x, bar_y).of_size(bar_width as u32, BAR_HEIGHT as u32),
+                Rgb(theme.colors.background),
+            );
+            let filled = (role.rating as f32 / MAX_BAR_RATING).clamp(0.0, 1.0) * bar_width as f32;
+            if filled >= 1.0 {
+                draw_filled_rect_mut(
+                    &mut image,
+                    Rect::at(bar_x, bar_y).of_size(filled as u32, BAR_HEIGHT as u32),
+                    bar_color,
+                );
+            }
+
+            self.draw_text(
+                &mut image,
+                Rect::at(rating_x, row.top()).of_size(PLAYER_RATING_WIDTH as u32, row.height()),
+                &format!("{} ({})", role.rating, role.rd),
+                &theme.text_font,
+                theme.text.player_rating,
+                text_color,
+            );
+        }
+
+        if let Some(footer) = layout.footer {
+            self.draw_text(
+                &mut image,
+                footer,
+                &format!("{}W {}L {}D", profile.wins, profile.losses, profile.draws),
+                &theme.text_font,
+                theme.text.title,
+                text_color,
+            );
+            if profile.flex {
+                self.draw_text(
+                    &mut image,
+                    footer,
+                    "FLEX",
+                    &theme.text_font,
+                    TextStyle {
+                        align: Align::Right,
+                        ..theme.text.title
+                    },
+                    bar_color,
+                );
+            }
+        }
+
+        encode(&image, format)
+    }
+
+    // team name, the badge next to it and the translucent panel behind the rows
+    #[allow(clippy::too_many_arguments)]
+    fn draw_column(
+        &self,
+        image: &mut RgbImage,
+        theme: &Theme,
+        column: &ColumnLayout,
+        color: Rgb<u8>,
+        name: &str,
+        badge: &str,
+        badge_color: Rgb<u8>,
+    ) {
+        self.draw_text(
+            image,
+            column.name,
+            &name.to_uppercase(),
+            &theme.text_font,
+            theme.text.team_name,
+            color,
+        );
+
+        draw_filled_rect_mut(image, column.rating, badge_color);
+        self.draw_text(
+            image,
+            column.rating,
+            badge,
+            &theme.text_font,
+            theme.text.rating,
+            Rgb(theme.colors.text),
+        );
+
+        fill_blended(image, column.panel, color, theme.colors.panel_opacity);
+    }
+
+    // role strip, avatar and name, leaving `reserved` pixels on the right for the caller
+    #[allow(clippy::too_many_arguments)]
+    fn draw_slot(
+        &self,
+        image: &mut RgbImage,
+        theme: &Theme,
+        row: Rect,
+        color: Rgb<u8>,
+        role: &Role,
+        name: Option<&str>,
+        avatar: Option<&RgbaImage>,
+        reserved: i32,
+    ) {
+        let role_rect = Rect::at(row.left(), row.top()).of_size(ROLE_WIDTH as u32, row.height());
+        draw_filled_rect_mut(image, role_rect, color);
+        draw_role_icon(image, role_rect, role, Rgb(theme.colors.text));
+
+        self.draw_player(image, theme, row, color, name, avatar, reserved);
+    }
+
+    // avatar and name to the right of the strip at the start of the row
+    #[allow(clippy::too_many_arguments)]
+    fn draw_player(
+        &self,
+        image: &mut RgbImage,
+        theme: &Theme,
+        row: Rect,
+        color: Rgb<u8>,
+        name: Option<&str>,
+        avatar: Option<&RgbaImage>,
+        reserved: i32,
+    ) {
+        let avatar_x = row.left() + ROLE_WIDTH + PADDING;
+        let avatar_y = row.top() + (row.height() as i32 - AVATAR_SIZE as i32) / 2;
+        match (avatar, name) {
+            (Some(avatar), _) => draw_avatar(image, avatar, avatar_x, avatar_y),
+            (None, Some(name)) => {
+                self.draw_avatar_placeholder(image, avatar_x, avatar_y, name, theme, color)
+            }
+            (None, None) => {}
+        }
+
+        let name_x = avatar_x + AVATAR_SIZE as i32 + PADDING;
+        let name_rect = Rect::at(name_x, row.top()).of_size(
+            (row.right() - 2 * PADDING - reserved - name_x) as u32,
+            row.height(),
+        );
+        self.draw_text(
+            image,
+            name_rect,
+            name.unwrap_or("Unknown"),
+            &theme.player_font,
+            theme.text.player,
+            Rgb(theme.colors.text),
+        );
+    }
+
+    // old rating, an arrow, the new rating and the colored delta
+    fn draw_rating_change(
+        &self,
+        image: &mut RgbImage,
+        theme: &Theme,
+        row: Rect,
+        player: &ResultPlayerImage,
+    ) {
+        let text_color = Rgb(theme.colors.text);
+        let style = theme.text.player_rating;
+        let rect = |x: i32, width: i32| Rect::at(x, row.top()).of_size(width as u32, row.height());
+
+        let delta_x = row.right() - PADDING - DELTA_WIDTH;
+        let new_x = delta_x - PADDING - CHANGE_RATING_WIDTH;
+        let arrow_x = new_x - ARROW_WIDTH;
+        let old_x = arrow_x - CHANGE_RATING_WIDTH;
+
+        let Some(new_rating) = player.new_rating else {
+            // players who didn't play long enough keep their rating
+            self.draw_text(
+                image,
+                rect(new_x, CHANGE_RATING_WIDTH),
+                &player.rating.to_string(),
+                &theme.text_font,
+                style,
+                text_color,
+            );
+            return;
+        };
+
+        self.draw_text(
+            image,
+            rect(old_x, CHANGE_RATING_WIDTH),
+            &player.rating.to_string(),
+            &theme.text_font,
+            style,
+            text_color,
+        );
+
+        let y = row.top() + row.height() as i32 / 2;
+        let size = ARROW_WIDTH / 4;
+        let center = arrow_x + ARROW_WIDTH / 2;
+        let arrow = [
+            Point::new(center - size, y - size),
+            Point::new(center + size, y),
+            Point::new(center - size, y + size),
+        ];
+        draw_polygon_mut(image, &arrow, text_color);
+
+        self.draw_text(
+            image,
+            rect(new_x, CHANGE_RATING_WIDTH),
+            &new_rating.to_string(),
+            &theme.text_font,
+            style,
+            text_color,
+        );
+
+        let delta = new_rating - player.rating;
+        let color = match delta {
+            delta if delta > 0 => theme.colors.positive,
+            delta if delta < 0 => theme.colors.negative,
+            _ => theme.colors.text,
+        };
+        self.draw_text(
+            image,
+            rect(delta_x, DELTA_WIDTH),
+            &format!("{:+}", delta),
+            &theme.text_font,
+            style,
+            Rgb(color),
+        );
+    }
+
+    fn draw_gaps(&self, image: &mut RgbImage, theme: &Theme, layout: &TeamsLayout) {
+        for gap in &layout.gaps {
+            self.draw_text(
+                image,
+                *gap,
+                "VS",
+                &theme.text_font,
+                theme.text.vs,
+                Rgb(theme.colors.vs),
+            );
+        }
+    }
+
+    // a darkened team colored circle with the first letter of the name
+    fn draw_avatar_placeholder(
+        &self,
+        image: &mut RgbImage,
+        x: i32,
+        y: i32,
+        name: &str,
+        theme: &Theme,
+        color: Rgb<u8>,
+    ) {
+        let radius = AVATAR_SIZE as i32 / 2;
+        draw_filled_circle_mut(
+            image,
+            (x + radius, y + radius),
+            radius,
+            Rgb(color.0.map(|c| c / 2)),
+        );
+
+        let initial = strip_emoji(name)
+            .chars()
+            .next()
+            .map(|c| c.to_uppercase().to_string())
+            .unwrap_or_default();
+        self.draw_text(
+            image,
+            Rect::at(x, y).of_size(AVATAR_SIZE, AVATAR_SIZE),
+            &initial,
+            &theme.text_font,
+            TextStyle {
+                size: AVATAR_SIZE as f32 * 0.8,
+                align: Align::Center,
+            },
+            Rgb(theme.colors.text),
+        );
+    }
+
+    // shrinks the text until it fits the width of the rect and centers it vertically
+    #[allow(clippy::too_many_arguments)]
+    fn draw_text(
+        &self,
+        image: &mut RgbImage,
+        rect: Rect,
+        text: &str,
+        font: &Font,
+        style: TextStyle,
+        color: Rgb<u8>,
+    ) {
+        let text = strip_emoji(text);
+        let fonts = std::iter::once(font)
+            .chain(&self.fallback_fonts)
+            .collect::<Vec<_>>();
+        let runs = text::runs(&fonts, &text);
+
+        let size = style.size;
+        let mut scale = Scale::uniform(size);
+        let width = text_width(&runs, scale);
+        if width > rect.width() as f32 {
+            scale = Scale::uniform(size * rect.width() as f32 / width);
+        }
+        scale = Scale::uniform(scale.x.min(rect.height() as f32));
+
+        let width = text_width(&runs, scale).round() as i32;
+        let metrics = font.v_metrics(scale);
+        let height = (metrics.ascent - metrics.descent) as i32;
+
+        let x = match style.align {
+            Align::Left => rect.left(),
+            Align::Center => rect.left() + (rect.width() as i32 - width) / 2,
+            Align::Right => rect.right() - width,
+        };
+        let y = rect.top() + (rect.height() as i32 - height) / 2;
+
+        draw_runs(image, color, x, y + metrics.ascent as i32, scale, &runs);
+    }
+}
+
+fn canvas(theme: &Theme, layout: &TeamsLayout) -> RgbImage {
+    match &theme.background {
+        Some(background) => cover(background, layout.width, layout.height),
+        None => RgbImage::from_pixel(layout.width, layout.height, Rgb(theme.colors.background)),
+    }
+}
+
+fn draw_role_icon(image: &mut RgbImage, rect: Rect, role: &Role, color: Rgb<u8>) {
+    let size = rect.width().min(rect.height()) as i32 / 2;
+    let x = rect.left() + rect.width() as i32 / 2;
+    let y = rect.top() + rect.height() as i32 / 2;
+
+    match role {
+        Role::Tank => {
+            let shield = [
+                Point::new(x - size / 2, y - size / 2),
+                Point::new(x + size / 2, y - size / 2),
+                Point::new(x + size / 2, y + size / 8),
+                Point::new(x, y + size / 2),
+                Point::new(x - size / 2, y + size / 8),
+            ];
+            draw_polygon_mut(image, &shield, color);
+        }
+        Role::Dps => {
+            let width = size / 5;
+            for i in -1..=1 {
+                let left = x + i * size / 3 - width / 2;
+                draw_filled_circle_mut(image, (left + width / 2, y - size / 4), width / 2, color);
+                draw_filled_rect_mut(
+                    image,
+                    Rect::at(left, y - size / 4).of_size(width as u32, (size * 5 / 8) as u32),
+                    color,
+                );
+                draw_filled_rect_mut(
+                    image,
+                    Rect::at(left, y + size / 2 - size / 8)
+                        .of_size(width as u32, (size / 8) as u32),
+                    color,
+                );
+            }
+        }
+        Role::Support => {
+            let width = size / 3;
+            draw_filled_rect_mut(
+                image,
+                Rect::at(x - width / 2, y - size / 2).of_size(width as u32, size as u32),
+                color,
+            );
+            draw_filled_rect_mut(
+                image,
+                Rect::at(x - size / 2, y - width / 2).of_size(size as u32, width as u32),
+                color,
+            );
+        }
+    }
+}
+
+// pastes the avatar cropped to a circle, blending it by its alpha
+fn draw_avatar(image: &mut RgbImage, avatar: &RgbaImage, x: i32, y: i32) {
+    let radius = avatar.width().min(avatar.height()) as f32 / 2.0;
+
+    for (ax, ay, pixel) in avatar.enumerate_pixels() {
+        let dx = ax as f32 + 0.5 - radius;
+        let dy = ay as f32 + 0.5 - radius;
+        // one pixel of antialiasing on the edge of the circle
+        let coverage = (radius - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+        let opacity = coverage * pixel[3] as f32 / 255.0;
+
+        let (px, py) = (x + ax as i32, y + ay as i32);
+        if opacity <= 0.0
+            || px < 0
+            || py < 0
+            || px >= image.width() as i32
+            || py >= image.height() as i32
+        {
+            continue;
+        }
+
+        let target = image.get_pixel_mut(px as u32, py as u32);
+        *target = Rgb([0, 1, 2]
+            .map(|i| (pixel[i] as f32 * opacity + target[i] as f32 * (1.0 - opacity)) as u8));
+    }
+}
+
+fn fill_blended(image: &mut RgbImage, rect: Rect, color: Rgb<u8>, opacity: f32) {
+    let opacity = opacity.clamp(0.0, 1.0);
+    let right = (rect.right() + 1).min(image.width() as i32);
+    let bottom = (rect.bottom() + 1).min(image.height() as i32);
+
+    for y in rect.top().max(0)..bottom {
+        for x in rect.left().max(0)..right {
+            let pixel = image.get_pixel_mut(x as u32, y as u32);
+            *pixel = Rgb([0, 1, 2]
+                .map(|i| (color[i] as f32 * opacity + pixel[i] as f32 * (1.0 - opacity)) as u8));
+        }
+    }
+}
+
+// scales the background to cover the whole image and crops the overflow evenly
+fn cover(background: &RgbImage, width: u32, height: u32) -> RgbImage {
+    let scale =
+        (width as f32 / background.width() as f32).max(height as f32 / background.height() as f32);
+    let resized = image::imageops::resize(
+        background,
+        ((background.width() as f32 * scale).ceil() as u32).max(width),
+        ((background.height() as f32 * scale).ceil() as u32).max(height),
+        image::imageops::FilterType::Triangle,
+    );
+
+    image::imageops::crop_imm(
+        &resized,
+        (resized.width() - width) / 2,
+        (resized.height() - height) / 2,
+        width,
+        height,
+    )
+    .to_image()
+}
+
+pub struct ImageGeneratorContainer;
+
+impl TypeMapKey for ImageGeneratorContainer {
+    type Value = Arc<ImageGenerator<'static>>;
+}
@@ -0,0 +1,163 @@
+use image::{Rgb, RgbImage};
+use imageproc::drawing::draw_text_mut;
+use rusttype::{Font, GlyphId, Scale};
+use std::fs;
+use tracing::log::{info, warn};
+
+// broad coverage of latin, cyrillic, greek and symbols, always the last font of the chain
+const BUNDLED_FALLBACK_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+// a piece of text drawn with a single font of the chain
+pub struct Run<'f, 'a> {
+    pub font: &'f Font<'a>,
+    pub text: String,
+}
+
+pub fn load_fallback_fonts<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<Font<'static>> {
+    paths
+        .into_iter()
+        .filter_map(
+            |path| match fs::read(path).ok().and_then(Font::try_from_vec) {
+                Some(font) => {
+                    info!("Loaded fallback font {}", path);
+                    Some(font)
+                }
+                None => {
+                    warn!("Could not load fallback font {}", path);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+pub fn bundled_fallback_font() -> Font<'static> {
+    Font::try_from_bytes(BUNDLED_FALLBACK_FONT).unwrap()
+}
+
+// rusttype can't draw color glyphs, so emoji and the characters joining them are dropped
+pub fn strip_emoji(text: &str) -> String {
+    let text = text.chars().filter(|c| !is_emoji(*c)).collect::<String>();
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF // pictographs, emoticons, flags and the rest of the emoji blocks
+            | 0x2600..=0x27BF // miscellaneous symbols and dingbats
+            | 0x2B00..=0x2BFF // arrows and stars used as emoji
+            | 0xFE00..=0xFE0F // variation selectors
+            | 0x200D // zero width joiner
+            | 0x20E3 // combining keycap
+            | 0xE0020..=0xE007F // tag sequences
+    )
+}
+
+// every character goes to the first font that has a glyph for it,
+// characters no font knows about stay with the first one
+pub fn runs<'f, 'a>(fonts: &[&'f Font<'a>], text: &str) -> Vec<Run<'f, 'a>> {
+    let mut runs: Vec<Run> = Vec::new();
+
+    for c in text.chars() {
+        let font = fonts
+            .iter()
+            .find(|font| font.glyph(c).id() != GlyphId(0))
+            .or(fonts.first())
+            .copied();
+        let Some(font) = font else {
+            continue;
+        };
+
+        match runs.last_mut() {
+            Some(run) if std::ptr::eq(run.font, font) => run.text.push(c),
+            _ => runs.push(Run {
+                font,
+                text: c.to_string(),
+            }),
+        }
+    }
+
+    runs
+}
+
+pub fn run_width(run: &Run, scale: Scale) -> f32 {
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for glyph in run.font.glyphs_for(run.text.chars()) {
+        if let Some(previous) = previous {
+            width += run.font.pair_kerning(scale, previous, glyph.id());
+        }
+        previous = Some(glyph.id());
+        width += glyph.scaled(scale).h_metrics().advance_width;
+    }
+
+    width
+}
+
+pub fn text_width(runs: &[Run], scale: Scale) -> f32 {
+    runs.iter().map(|run| run_width(run, scale)).sum()
+}
+
+// draws the runs one after another on a shared baseline
+pub fn draw_runs(
+    image: &mut RgbImage,
+    color: Rgb<u8>,
+    x: i32,
+    baseline: i32,
+    scale: Scale,
+    runs: &[Run],
+) {
+    let mut x = x as f32;
+
+    for run in runs {
+        let ascent = run.font.v_metrics(scale).ascent;
+        draw_text_mut(
+            image,
+            color,
+            x.round() as i32,
+            baseline - ascent.round() as i32,
+            scale,
+            run.font,
+            &run.text,
+        );
+        x += run_width(run, scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title_font() -> Font<'static> {
+        Font::try_from_bytes(include_bytes!("../../assets/fonts/big-noodle-titling.ttf")).unwrap()
+    }
+
+    #[test]
+    fn missing_glyphs_go_to_the_fallback_font() {
+        let title = title_font();
+        let fallback = bundled_fallback_font();
+
+        let runs = runs(&[&title, &fallback], "GG Жора");
+
+        assert_eq!(
+            runs.iter()
+                .map(|run| (std::ptr::eq(run.font, &fallback), run.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(false, "GG "), (true, "Жора")]
+        );
+    }
+
+    #[test]
+    fn glyphs_no_font_has_stay_with_the_first_font() {
+        let title = title_font();
+        let fallback = bundled_fallback_font();
+
+        let runs = runs(&[&title, &fallback], "漢字");
+
+        assert_eq!(runs.len(), 1);
+        assert!(std::ptr::eq(runs[0].font, &title));
+    }
+}
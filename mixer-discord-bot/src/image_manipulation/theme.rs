@@ -0,0 +1,202 @@
+use image::{Rgb, RgbImage};
+use rusttype::Font;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::log::{info, warn};
+
+const MANIFEST: &str = "theme.json";
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct TextStyle {
+    pub size: f32,
+    #[serde(default)]
+    pub align: Align,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TextStyles {
+    pub team_name: TextStyle,
+    pub rating: TextStyle,
+    pub player: TextStyle,
+    pub player_rating: TextStyle,
+    pub vs: TextStyle,
+    pub role_gap: TextStyle,
+    pub title: TextStyle,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ThemeColors {
+    pub background: [u8; 3],
+    pub rating_background: [u8; 3],
+    pub text: [u8; 3],
+    pub vs: [u8; 3],
+    pub positive: [u8; 3],
+    pub negative: [u8; 3],
+    pub bar: [u8; 3],
+    pub ranks: Vec<[u8; 3]>,
+    pub teams: Vec<[u8; 3]>,
+    pub panel_opacity: f32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ThemeManifest {
+    name: Option<String>,
+    background: Option<String>,
+    player_font: Option<String>,
+    text_font: Option<String>,
+    colors: ThemeColors,
+    text: TextStyles,
+}
+
+pub struct Theme<'a> {
+    pub player_font: Font<'a>,
+    pub text_font: Font<'a>,
+    pub background: Option<RgbImage>,
+    pub colors: ThemeColors,
+    pub text: TextStyles,
+}
+
+impl Default for TextStyles {
+    fn default() -> Self {
+        let style = |size, align| TextStyle { size, align };
+
+        Self {
+            team_name: style(86.5, Align::Left),
+            rating: style(86.5, Align::Center),
+            player: style(60.0, Align::Left),
+            player_rating: style(40.0, Align::Right),
+            vs: style(120.0, Align::Center),
+            role_gap: style(48.0, Align::Left),
+            title: style(56.0, Align::Left),
+        }
+    }
+}
+
+impl Default for ThemeColors {
+    fn default() -> Self {
+        Self {
+            background: [40, 42, 54],
+            rating_background: [70, 72, 86],
+            text: [255, 255, 255],
+            vs: [255, 160, 0],
+            positive: [80, 200, 120],
+            negative: [235, 80, 80],
+            bar: [255, 160, 0],
+            ranks: vec![[230, 190, 60], [200, 200, 210], [205, 127, 50]],
+            teams: vec![[60, 64, 230], [225, 50, 60]],
+            panel_opacity: 0.6,
+        }
+    }
+}
+
+impl ThemeColors {
+    pub fn team(&self, index: usize) -> Rgb<u8> {
+        match self.teams.len() {
+            0 => Rgb(self.text),
+            len => Rgb(self.teams[index % len]),
+        }
+    }
+}
+
+impl<'a> Theme<'a> {
+    pub fn new(player_font: Font<'a>, text_font: Font<'a>) -> Self {
+        Self {
+            player_font,
+            text_font,
+            background: None,
+            colors: ThemeColors::default(),
+            text: TextStyles::default(),
+        }
+    }
+}
+
+impl Theme<'static> {
+    // anything the manifest leaves out is taken from the built-in theme
+    pub fn load(dir: &Path, fallback: &Theme<'static>) -> Result<(String, Self), String> {
+        let manifest = fs::read_to_string(dir.join(MANIFEST))
+            .map_err(|why| format!("could not read {}: {}", MANIFEST, why))?;
+        let manifest = serde_json::from_str::<ThemeManifest>(&manifest)
+            .map_err(|why| format!("invalid {}: {}", MANIFEST, why))?;
+
+        let name = match manifest.name {
+            Some(name) => name,
+            None => dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or("the theme has no name")?
+                .to_string(),
+        };
+
+        let font = |path: Option<String>, fallback: &Font<'static>| match path {
+            Some(path) => fs::read(dir.join(&path))
+                .ok()
+                .and_then(Font::try_from_vec)
+                .ok_or(format!("could not load font {}", path)),
+            None => Ok(fallback.clone()),
+        };
+        let player_font = font(manifest.player_font, &fallback.player_font)?;
+        let text_font = font(manifest.text_font, &fallback.text_font)?;
+
+        let background = match manifest.background {
+            Some(path) => Some(
+                image::open(dir.join(&path))
+                    .map_err(|why| format!("could not load background {}: {}", path, why))?
+                    .to_rgb8(),
+            ),
+            None => None,
+        };
+
+        Ok((
+            name,
+            Self {
+                player_font,
+                text_font,
+                background,
+                colors: manifest.colors,
+                text: manifest.text,
+            },
+        ))
+    }
+}
+
+pub fn load_themes(dir: &Path, fallback: &Theme<'static>) -> HashMap<String, Theme<'static>> {
+    let mut themes = HashMap::new();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(why) => {
+            warn!("Could not read the themes directory {:?}: {}", dir, why);
+            return themes;
+        }
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if !path.is_dir() {
+            continue;
+        }
+
+        match Theme::load(&path, fallback) {
+            Ok((name, theme)) => {
+                info!("Loaded theme {}", name);
+                themes.insert(name, theme);
+            }
+            Err(why) => warn!("Skipping theme {:?}: {}", path, why),
+        }
+    }
+
+    themes
+}
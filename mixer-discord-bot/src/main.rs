@@ -5,6 +5,11 @@ mod image_manipulation;
 mod mixer;
 
 use bot::commands::creator::CreatorCommand;
+use image_manipulation::avatar::{
+    AvatarCache, AvatarCacheContainer, HttpAvatarSource, AVATAR_CACHE_SIZE,
+};
+use image_manipulation::text::{bundled_fallback_font, load_fallback_fonts};
+use image_manipulation::theme::{load_themes, Theme};
 use image_manipulation::{ImageGenerator, ImageGeneratorContainer};
 use migration::{Migrator, MigratorTrait};
 use rusttype::Font;
@@ -14,15 +19,21 @@ use serenity::Client;
 use shuttle_runtime::Context;
 use shuttle_secrets::SecretStore;
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::bot::commands::leaderboard::LeaderboardCommand;
 use crate::bot::commands::lobby::LobbyCommand;
 use crate::bot::commands::ping::PingCommand;
 use crate::bot::commands::preference::PreferenceCommand;
+use crate::bot::commands::profile::ProfileCommand;
+use crate::bot::commands::queue::QueueCommand;
 use crate::bot::commands::rating::RatingCommand;
 use crate::bot::commands::settings::SettingsCommand;
 use crate::bot::MixerBot;
+use crate::database::queries::prelude::LobbyQuery;
 use crate::database::{DatabaseContainer, MixerDatabase};
 
 struct CreatorContainer;
@@ -52,8 +63,11 @@ async fn serenity(
     bot.add_command(LobbyCommand);
     bot.add_command(RatingCommand);
     bot.add_command(PreferenceCommand);
+    bot.add_command(QueueCommand);
     bot.add_command(SettingsCommand);
     bot.add_command(CreatorCommand);
+    bot.add_command(LeaderboardCommand);
+    bot.add_command(ProfileCommand);
 
     let client = Client::builder(&token, GatewayIntents::all())
         .event_handler(bot)
@@ -68,6 +82,8 @@ async fn serenity(
         Migrator::up(db.connection(), None)
             .await
             .expect("Could not run migrations");
+        // sessions don't survive a restart
+        LobbyQuery::reset_statuses(db.connection()).await;
         data.insert::<DatabaseContainer>(Arc::new(RwLock::new(db)));
 
         let creator = UserId::from(
@@ -79,20 +95,32 @@ async fn serenity(
         );
         data.insert::<CreatorContainer>(Arc::new(creator));
 
-        let image_generator = ImageGenerator {
-            player_font: Font::try_from_bytes(include_bytes!(
-                "../assets/fonts/big-noodle-too-oblique.ttf"
-            ))
-            .unwrap(),
-            text_font: Font::try_from_bytes(include_bytes!(
-                "../assets/fonts/big-noodle-titling.ttf"
-            ))
-            .unwrap(),
-            teams_image: image::load_from_memory(include_bytes!("../assets/images/teams.png"))
+        let mut default_theme = Theme::new(
+            Font::try_from_bytes(include_bytes!("../assets/fonts/big-noodle-too-oblique.ttf"))
+                .unwrap(),
+            Font::try_from_bytes(include_bytes!("../assets/fonts/big-noodle-titling.ttf")).unwrap(),
+        );
+        default_theme.background = Some(
+            image::load_from_memory(include_bytes!("../assets/images/teams.png"))
                 .unwrap()
                 .to_rgb8(),
+        );
+        let themes = match secret_store.get("THEMES_DIR") {
+            Some(dir) => load_themes(Path::new(&dir), &default_theme),
+            None => HashMap::new(),
         };
+        // configured fonts, e.g. for CJK, come before the bundled one
+        let mut fallback_fonts = secret_store
+            .get("FALLBACK_FONTS")
+            .map(|paths| load_fallback_fonts(paths.split(',').map(str::trim)))
+            .unwrap_or_default();
+        fallback_fonts.push(bundled_fallback_font());
+        let image_generator = ImageGenerator::new(default_theme, themes, fallback_fonts);
         data.insert::<ImageGeneratorContainer>(Arc::new(image_generator));
+        data.insert::<AvatarCacheContainer>(Arc::new(AvatarCache::new(
+            Box::new(HttpAvatarSource::new()),
+            AVATAR_CACHE_SIZE,
+        )));
     }
 
     Ok(client.into())
@@ -0,0 +1,155 @@
+use sea_orm::Iterable;
+
+use crate::mixer::team::Team;
+use entity::prelude::Role;
+
+#[derive(Debug, Clone)]
+pub struct Draft {
+    pub teams: [Team; 2],
+    pub captains: [usize; 2],
+
+    pool: Vec<usize>,
+    team_size: usize,
+    pick: usize,
+}
+
+impl Draft {
+    pub fn new(players_count: usize, captains: [usize; 2], slots: Vec<Role>) -> Self {
+        Self {
+            teams: [Team::new(slots.clone()), Team::new(slots.clone())],
+            captains,
+            pool: (0..players_count).collect(),
+            team_size: slots.len(),
+            pick: 0,
+        }
+    }
+
+    pub fn current_team(&self) -> Option<usize> {
+        // snake order: A, B, B, A, A, B, B, ...
+        let team = if self.pick == 0 {
+            0
+        } else {
+            self.pick.div_ceil(2) % 2
+        };
+
+        [team, 1 - team]
+            .into_iter()
+            .find(|team| !self.available_players(*team).is_empty())
+    }
+
+    pub fn available_players(&self, team: usize) -> Vec<usize> {
+        let remaining = self.team_size - self.teams[team].count();
+        if remaining == 0 {
+            return Vec::new();
+        }
+
+        let captain = self.captains[team];
+        let other_captain = self.captains[1 - team];
+
+        // the captain has to save the last slot for themselves
+        if remaining == 1 && self.pool.contains(&captain) {
+            return vec![captain];
+        }
+
+        self.pool
+            .iter()
+            .copied()
+            .filter(|index| *index != other_captain)
+            .collect()
+    }
+
+    pub fn open_roles(&self, team: usize) -> Vec<Role> {
+        Role::iter()
+            .filter(|role| self.teams[team].has_slot(role))
+            .collect()
+    }
+
+    pub fn pick(&mut self, team: usize, index: usize, role: &Role) -> bool {
+        if self.current_team() != Some(team)
+            || !self.available_players(team).contains(&index)
+            || !self.teams[team].has_slot(role)
+        {
+            return false;
+        }
+
+        self.teams[team].add_player(index, role);
+        self.pool.retain(|i| *i != index);
+        self.pick += 1;
+
+        true
+    }
+
+    pub fn into_teams(self) -> (Team, Team) {
+        let [team1, team2] = self.teams;
+        (team1, team2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draft() -> Draft {
+        Draft::new(6, [0, 1], vec![Role::Tank, Role::Dps, Role::Support])
+    }
+
+    // picks the highest available index, so captains are never chosen before they have to be
+    fn pick_last(draft: &mut Draft) -> (usize, usize) {
+        let team = draft.current_team().unwrap();
+        let index = *draft.available_players(team).last().unwrap();
+        let role = draft.open_roles(team)[0];
+        assert!(draft.pick(team, index, &role));
+
+        (team, index)
+    }
+
+    #[test]
+    fn teams_pick_in_snake_order() {
+        let mut draft = draft();
+
+        let order = (0..6).map(|_| pick_last(&mut draft).0).collect::<Vec<_>>();
+
+        assert_eq!(order, vec![0, 1, 1, 0, 0, 1]);
+        assert_eq!(draft.current_team(), None);
+    }
+
+    #[test]
+    fn captains_pick_themselves_last() {
+        let mut draft = draft();
+
+        let picks = (0..6).map(|_| pick_last(&mut draft)).collect::<Vec<_>>();
+
+        for (team, captain) in [(0, 0), (1, 1)] {
+            let last = picks.iter().rfind(|(t, _)| *t == team).unwrap();
+            assert_eq!(last.1, captain);
+        }
+        let (team1, team2) = draft.into_teams();
+        assert!(team1.players.values().any(|index| *index == Some(0)));
+        assert!(team2.players.values().any(|index| *index == Some(1)));
+    }
+
+    #[test]
+    fn last_slot_is_kept_for_the_captain() {
+        let mut draft = draft();
+        // A, B, B, A: the first team has a single slot left
+        for _ in 0..4 {
+            pick_last(&mut draft);
+        }
+
+        assert_eq!(draft.current_team(), Some(0));
+        assert_eq!(draft.available_players(0), vec![0]);
+        let role = draft.open_roles(0)[0];
+        assert!(!draft.pick(0, 2, &role));
+        assert!(draft.pick(0, 0, &role));
+    }
+
+    #[test]
+    fn picks_out_of_turn_are_rejected() {
+        let mut draft = draft();
+
+        assert!(!draft.pick(1, 5, &Role::Tank));
+        assert!(!draft.pick(0, 1, &Role::Tank));
+        assert!(draft.pick(0, 5, &Role::Tank));
+        assert!(!draft.pick(0, 4, &Role::Dps));
+    }
+}
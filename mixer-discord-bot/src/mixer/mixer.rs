@@ -1,9 +1,43 @@
 use itertools::Itertools;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use sea_orm::prelude::DateTime;
+use sea_orm::Iterable;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 
 use crate::mixer::player::Player;
 use crate::mixer::team::Team;
 use entity::prelude::Role;
+use entity::{mixes, players};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MixMode {
+    Role,
+    Open,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MixRequest {
+    pub mode: MixMode,
+    pub slots: Vec<Role>,
+    pub matches: usize,
+    pub now: DateTime,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixError {
+    NotEnoughPlayers {
+        matches: usize,
+        needed: usize,
+        available: usize,
+    },
+    NoComposition,
+}
 
 #[derive(Debug)]
 struct PlayerRoleEntry {
@@ -20,6 +54,14 @@ impl PartialEq for PlayerRoleEntry {
     }
 }
 
+// the inputs stored with a mix, running them through `mix` again gives the same teams
+pub fn stored_inputs(mix: mixes::Model) -> Option<(MixRequest, Vec<players::Model>)> {
+    let request = serde_json::from_value::<MixRequest>(mix.request).ok()?;
+    let players = serde_json::from_value::<Vec<players::Model>>(mix.players).ok()?;
+
+    Some((request, players))
+}
+
 fn get_role_entries(
     entries: Vec<PlayerRoleEntry>,
 ) -> (
@@ -47,10 +89,67 @@ fn get_combinations(entries: &[PlayerRoleEntry], count: usize) -> Vec<Vec<&Playe
         .collect_vec()
 }
 
-pub fn mix_players(players: &[Player], slots: Vec<Role>) -> Option<(Team, Team)> {
+impl fmt::Display for MixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MixError::NotEnoughPlayers {
+                matches,
+                needed,
+                available,
+            } => write!(
+                f,
+                "Not enough players for {} {}! {} are needed, but only {} are ready.",
+                matches,
+                if *matches == 1 { "match" } else { "matches" },
+                needed,
+                available
+            ),
+            MixError::NoComposition => write!(f, "Fair teams' composition could not be found!"),
+        }
+    }
+}
+
+impl std::error::Error for MixError {}
+
+pub fn mix(players: &[Player], request: &MixRequest) -> Result<Vec<(Team, Team)>, MixError> {
+    let matches = request.matches.max(1);
+    let needed = request.slots.len() * matches * 2;
+    if players.len() < needed {
+        return Err(MixError::NotEnoughPlayers {
+            matches,
+            needed,
+            available: players.len(),
+        });
+    }
+
+    // the seed only decides the order players are considered in, which breaks ties
+    let mut order = (0..players.len()).collect_vec();
+    order.shuffle(&mut ChaCha8Rng::seed_from_u64(request.seed));
+
+    let shuffled = order.iter().map(|i| players[*i].clone()).collect_vec();
+    let matches = match request.mode {
+        MixMode::Role => mix_matches(
+            &shuffled,
+            request.slots.clone(),
+            request.matches,
+            request.now,
+        ),
+        MixMode::Open => {
+            mix_matches_open(&shuffled, request.slots.len(), request.matches, request.now)
+        }
+    }
+    .ok_or(MixError::NoComposition)?;
+
+    Ok(matches
+        .into_iter()
+        .map(|(team1, team2)| (team1.remap(&order), team2.remap(&order)))
+        .collect())
+}
+
+pub fn mix_players(players: &[Player], slots: Vec<Role>, now: DateTime) -> Option<(Team, Team)> {
     let players = players.to_vec();
 
-    let entries = calculate_priorities(&players);
+    let entries = calculate_priorities(&players, now);
     let (tanks, dps, supports) = get_role_entries(entries);
 
     let tank_count = slots.iter().filter(|r| **r == Role::Tank).count();
@@ -216,11 +315,260 @@ pub fn mix_players(players: &[Player], slots: Vec<Role>) -> Option<(Team, Team)>
     }
 }
 
-fn calculate_priorities(players: &[Player]) -> Vec<PlayerRoleEntry> {
+pub fn mix_players_open(players: &[Player], size: usize, now: DateTime) -> Option<(Team, Team)> {
+    if size == 0 || players.len() < size * 2 {
+        return None;
+    }
+
+    let chosen = (0..players.len())
+        .sorted_by(|a, b| {
+            players[*b]
+                .priority_points(now)
+                .partial_cmp(&players[*a].priority_points(now))
+                .unwrap_or(Ordering::Equal)
+        })
+        .take(size * 2)
+        .collect_vec();
+    let roles = chosen
+        .iter()
+        .map(|index| (*index, players[*index].open_queue_role()))
+        .collect_vec();
+
+    let mut best_teams = None;
+    let mut best_diff = None;
+
+    let threshold = 150.0;
+
+    // the first chosen player always goes to team 1, otherwise every split is checked twice
+    for team1_combo in roles[1..].iter().combinations(size - 1) {
+        let mut team1 = Team::new_open(size);
+        let mut team2 = Team::new_open(size);
+
+        for (index, role) in &roles {
+            if *index == roles[0].0 || team1_combo.iter().any(|(i, _)| i == index) {
+                team1.add_player(*index, role);
+            } else {
+                team2.add_player(*index, role);
+            }
+        }
+
+        let diff = (team1.full_rating(players).value - team2.full_rating(players).value).abs();
+
+        if diff < best_diff.unwrap_or(f32::MAX) {
+            if diff < threshold {
+                return Some((team1, team2));
+            }
+
+            best_teams = Some((team1, team2));
+            best_diff = Some(diff);
+        }
+    }
+
+    best_teams
+}
+
+pub fn mix_matches(
+    players: &[Player],
+    slots: Vec<Role>,
+    matches: usize,
+    now: DateTime,
+) -> Option<Vec<(Team, Team)>> {
+    if matches <= 1 {
+        return mix_players(players, slots, now).map(|teams| vec![teams]);
+    }
+
+    let mut entries = calculate_priorities(players, now);
+    entries.sort_by(|a, b| {
+        b.priority
+            .partial_cmp(&a.priority)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let capacity = Role::iter()
+        .map(|role| {
+            let count = slots.iter().filter(|r| **r == role).count();
+            (role, count * matches * 2)
+        })
+        .collect::<HashMap<_, _>>();
+
+    // every player keeps their roles best first, players are tried best first
+    let mut roles = BTreeMap::<usize, Vec<Role>>::new();
+    for entry in &entries {
+        roles.entry(entry.index).or_default().push(entry.role);
+    }
+    let order = entries
+        .iter()
+        .map(|entry| entry.index)
+        .unique()
+        .collect_vec();
+
+    let total = capacity.values().sum::<usize>();
+    let mut assigned = BTreeMap::new();
+    for index in order {
+        if assigned.len() == total {
+            break;
+        }
+
+        assign_role(index, &roles, &capacity, &mut assigned, &mut HashSet::new());
+    }
+
+    if assigned.len() < total {
+        return None;
+    }
+
+    let mut teams = vec![Vec::new(); matches * 2];
+    for role in Role::iter() {
+        let role_players = assigned
+            .iter()
+            .filter(|(_, r)| **r == role)
+            .map(|(index, _)| *index)
+            .sorted_by(|a, b| players[*b].rating(&role).cmp(&players[*a].rating(&role)))
+            .collect_vec();
+
+        for (team, index) in snake_order(role_players.len(), teams.len())
+            .into_iter()
+            .zip(role_players)
+        {
+            teams[team].push((index, role));
+        }
+    }
+
+    pair_teams(players, teams, |players| {
+        mix_players(players, slots.clone(), now)
+    })
+}
+
+pub fn mix_matches_open(
+    players: &[Player],
+    size: usize,
+    matches: usize,
+    now: DateTime,
+) -> Option<Vec<(Team, Team)>> {
+    if matches <= 1 {
+        return mix_players_open(players, size, now).map(|teams| vec![teams]);
+    }
+
+    if size == 0 || players.len() < size * matches * 2 {
+        return None;
+    }
+
+    let chosen = (0..players.len())
+        .sorted_by(|a, b| {
+            players[*b]
+                .priority_points(now)
+                .partial_cmp(&players[*a].priority_points(now))
+                .unwrap_or(Ordering::Equal)
+        })
+        .take(size * matches * 2)
+        .sorted_by(|a, b| {
+            let a = &players[*a];
+            let b = &players[*b];
+            b.rating(&b.open_queue_role())
+                .cmp(&a.rating(&a.open_queue_role()))
+        })
+        .collect_vec();
+
+    let mut teams = vec![Vec::new(); matches * 2];
+    for (team, index) in snake_order(chosen.len(), teams.len())
+        .into_iter()
+        .zip(chosen)
+    {
+        teams[team].push((index, players[index].open_queue_role()));
+    }
+
+    pair_teams(players, teams, |players| {
+        mix_players_open(players, size, now)
+    })
+}
+
+// finds a role for the player, moving already assigned players to their other roles if needed
+fn assign_role(
+    index: usize,
+    roles: &BTreeMap<usize, Vec<Role>>,
+    capacity: &HashMap<Role, usize>,
+    assigned: &mut BTreeMap<usize, Role>,
+    visited: &mut HashSet<Role>,
+) -> bool {
+    for role in &roles[&index] {
+        if !visited.insert(*role) {
+            continue;
+        }
+
+        let holders = assigned
+            .iter()
+            .filter(|(_, r)| *r == role)
+            .map(|(index, _)| *index)
+            .collect_vec();
+
+        if holders.len() < capacity[role]
+            || holders
+                .into_iter()
+                .any(|holder| assign_role(holder, roles, capacity, assigned, visited))
+        {
+            assigned.insert(index, *role);
+            return true;
+        }
+    }
+
+    false
+}
+
+fn snake_order(count: usize, teams: usize) -> Vec<usize> {
+    (0..count)
+        .map(|i| {
+            let round = i / teams;
+            let position = i % teams;
+            if round.is_multiple_of(2) {
+                position
+            } else {
+                teams - 1 - position
+            }
+        })
+        .collect()
+}
+
+fn pair_teams<F>(
+    players: &[Player],
+    teams: Vec<Vec<(usize, Role)>>,
+    mix: F,
+) -> Option<Vec<(Team, Team)>>
+where
+    F: Fn(&[Player]) -> Option<(Team, Team)>,
+{
+    let average = |team: &Vec<(usize, Role)>| {
+        team.iter()
+            .map(|(index, role)| players[*index].rating(role).value)
+            .sum::<f32>()
+            / team.len().max(1) as f32
+    };
+
+    // closest teams play each other, then each match is re-balanced on its own
+    teams
+        .into_iter()
+        .sorted_by(|a, b| {
+            average(b)
+                .partial_cmp(&average(a))
+                .unwrap_or(Ordering::Equal)
+        })
+        .tuples()
+        .map(|(team1, team2)| {
+            let indices = team1
+                .into_iter()
+                .chain(team2)
+                .map(|(index, _)| index)
+                .collect_vec();
+            let match_players = indices.iter().map(|i| players[*i].clone()).collect_vec();
+
+            mix(&match_players).map(|(team1, team2)| (team1.remap(&indices), team2.remap(&indices)))
+        })
+        .collect()
+}
+
+fn calculate_priorities(players: &[Player], now: DateTime) -> Vec<PlayerRoleEntry> {
     let mut priorities = Vec::new();
 
     for (i, player) in players.iter().enumerate() {
-        for (role, priority) in player.base_priority() {
+        for (role, priority) in player.base_priority(now) {
             priorities.push(PlayerRoleEntry {
                 index: i,
                 role,
@@ -231,3 +579,202 @@ fn calculate_priorities(players: &[Player]) -> Vec<PlayerRoleEntry> {
 
     priorities
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOTS: [Role; 3] = [Role::Tank, Role::Dps, Role::Support];
+
+    fn model(id: usize, roles: &[Role], rating: f32) -> players::Model {
+        let role = |i: usize| roles.get(i).copied();
+
+        players::Model {
+            id: id as i32,
+            discord_id: id as i64 + 1,
+            bn_name: None,
+            bn_tag: None,
+            last_played: None,
+            tank_rating: rating,
+            tank_rd: 100.0,
+            tank_volatility: 0.06,
+            dps_rating: rating,
+            dps_rd: 100.0,
+            dps_volatility: 0.06,
+            support_rating: rating,
+            support_rd: 100.0,
+            support_volatility: 0.06,
+            flex: false,
+            primary_role: role(0),
+            secondary_role: role(1),
+            tertiary_role: role(2),
+            no_shows: 0,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+
+    fn player(id: usize, roles: &[Role], rating: f32) -> Player {
+        Player::new(model(id, roles, rating))
+    }
+
+    fn models() -> Vec<players::Model> {
+        let roles = [
+            vec![Role::Tank, Role::Dps],
+            vec![Role::Dps, Role::Support],
+            vec![Role::Support, Role::Tank],
+            vec![Role::Dps],
+        ];
+
+        (0..14)
+            .map(|i| {
+                model(
+                    i,
+                    &roles[i % roles.len()],
+                    2000.0 + (i * 7 % 11) as f32 * 60.0,
+                )
+            })
+            .collect()
+    }
+
+    fn request(seed: u64) -> MixRequest {
+        MixRequest {
+            mode: MixMode::Role,
+            slots: SLOTS.to_vec(),
+            matches: 2,
+            now: now(),
+            seed,
+        }
+    }
+
+    fn lineups(matches: &[(Team, Team)]) -> Vec<Vec<Option<usize>>> {
+        matches
+            .iter()
+            .flat_map(|(team1, team2)| [team1, team2])
+            .map(|team| team.players.values().copied().collect())
+            .collect()
+    }
+
+    fn now() -> DateTime {
+        DateTime::from_timestamp_opt(1_690_000_000, 0).unwrap()
+    }
+
+    fn assigned_role(teams: &[(Team, Team)], index: usize) -> Option<Role> {
+        teams
+            .iter()
+            .flat_map(|(team1, team2)| team1.players.iter().chain(team2.players.iter()))
+            .find(|(_, i)| **i == Some(index))
+            .map(|((role, _), _)| *role)
+    }
+
+    #[test]
+    fn mix_matches_moves_flexible_players_to_free_roles() {
+        // taking the tank/dps players as tanks first leaves nobody for dps
+        let players = (0..12)
+            .map(|i| match i {
+                0..=3 => player(i, &[Role::Tank, Role::Dps], 2500.0 + i as f32 * 10.0),
+                4..=7 => player(i, &[Role::Tank], 2500.0 + i as f32 * 10.0),
+                _ => player(i, &[Role::Support], 2500.0 + i as f32 * 10.0),
+            })
+            .collect_vec();
+
+        let teams = mix_matches(&players, SLOTS.to_vec(), 2, now()).unwrap();
+
+        assert_eq!(teams.len(), 2);
+        for index in 0..4 {
+            assert_eq!(assigned_role(&teams, index), Some(Role::Dps));
+        }
+        for index in 4..8 {
+            assert_eq!(assigned_role(&teams, index), Some(Role::Tank));
+        }
+    }
+
+    #[test]
+    fn mix_reports_missing_players() {
+        let players = (0..10)
+            .map(|i| player(i, &[Role::Tank, Role::Dps, Role::Support], 2500.0))
+            .collect_vec();
+        assert!(matches!(
+            mix(&players, &request(1)),
+            Err(MixError::NotEnoughPlayers {
+                matches: 2,
+                needed: 12,
+                available: 10,
+            })
+        ));
+    }
+
+    #[test]
+    fn mix_is_deterministic_for_a_seed() {
+        let players = models().into_iter().map(Player::new).collect_vec();
+
+        let first = mix(&players, &request(42)).unwrap();
+        let second = mix(&players, &request(42)).unwrap();
+
+        assert_eq!(lineups(&first), lineups(&second));
+    }
+
+    #[test]
+    fn stored_mix_replays_to_the_same_teams() {
+        let models = models();
+        let request = request(7);
+        let players = models.iter().cloned().map(Player::new).collect_vec();
+        let original = mix(&players, &request).unwrap();
+
+        // stored the same way as MixQuery::create does
+        let stored = mixes::Model {
+            id: 1,
+            guild_id: 1,
+            lobby_id: 1,
+            created_at: request.now,
+            request: serde_json::to_value(&request).unwrap(),
+            players: serde_json::to_value(&models).unwrap(),
+        };
+        let (replayed_request, replayed_models) = stored_inputs(stored).unwrap();
+        let replayed_players = replayed_models.into_iter().map(Player::new).collect_vec();
+        let replayed = mix(&replayed_players, &replayed_request).unwrap();
+
+        assert_eq!(replayed_request, request);
+        assert_eq!(lineups(&replayed), lineups(&original));
+    }
+
+    #[test]
+    fn open_mix_fills_every_slot_with_preferred_roles() {
+        let players = models().into_iter().map(Player::new).collect_vec();
+        let request = MixRequest {
+            mode: MixMode::Open,
+            ..request(3)
+        };
+
+        let matches = mix(&players, &request).unwrap();
+
+        assert_eq!(matches.len(), 2);
+        let mut mixed = HashSet::new();
+        for team in matches.iter().flat_map(|(team1, team2)| [team1, team2]) {
+            assert_eq!(team.count(), SLOTS.len());
+            for ((role, _), index) in &team.players {
+                let index = index.expect("open teams have no empty slots");
+                assert!(mixed.insert(index));
+                assert_eq!(*role, players[index].open_queue_role());
+            }
+        }
+    }
+
+    #[test]
+    fn open_mix_is_deterministic_for_a_seed() {
+        let players = models().into_iter().map(Player::new).collect_vec();
+        let request = MixRequest {
+            mode: MixMode::Open,
+            ..request(11)
+        };
+
+        let first = mix(&players, &request).unwrap();
+        let second = mix(&players, &request).unwrap();
+
+        assert_eq!(lineups(&first), lineups(&second));
+        for index in 0..players.len() {
+            assert_eq!(assigned_role(&first, index), assigned_role(&second, index));
+        }
+    }
+}
@@ -1,4 +1,6 @@
+pub mod draft;
 pub mod mixer;
+pub mod participant;
 pub mod player;
 pub mod rating;
 pub mod team;
@@ -0,0 +1,41 @@
+use sea_orm::prelude::DateTime;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::UserId;
+
+use entity::prelude::Role;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Participant {
+    pub team: usize,
+    pub role: Role,
+    #[serde(skip)]
+    pub index: usize,
+    pub discord_id: UserId,
+    pub joined_at: DateTime,
+    pub left_at: Option<DateTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Substitution {
+    pub team: usize,
+    pub role: Role,
+    pub leaving: UserId,
+    pub joining: UserId,
+    pub at: DateTime,
+}
+
+impl Participant {
+    pub fn is_playing(&self) -> bool {
+        self.left_at.is_none()
+    }
+
+    pub fn played_share(&self, started_at: DateTime, finished_at: DateTime) -> f32 {
+        let total = (finished_at - started_at).num_seconds();
+        if total <= 0 {
+            return 1.0;
+        }
+
+        let played = (self.left_at.unwrap_or(finished_at) - self.joined_at).num_seconds();
+        (played as f32 / total as f32).clamp(0.0, 1.0)
+    }
+}
@@ -1,13 +1,18 @@
 use sea_orm::entity::prelude::*;
 use sea_orm::Iterable;
 use serenity::model::id::UserId;
-use sqlx::types::chrono::Utc;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::mixer::rating::Rating;
 use entity::players;
 use entity::prelude::Role;
 
+pub const CALIBRATED_RD: f32 = 200.0;
+const UNCALIBRATED_RD: f32 = 300.0;
+const OFF_ROLE_PENALTY: f32 = 250.0;
+const NO_SHOW_PENALTY: f32 = 25.0;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Player {
     pub id: i32,
@@ -19,6 +24,7 @@ pub struct Player {
     pub ranks: HashMap<Role, Rating>,
     pub flex: bool,
     pub priority_roles: Vec<Option<Role>>,
+    pub no_shows: i32,
 }
 
 impl Player {
@@ -57,14 +63,22 @@ impl Player {
                 model.secondary_role,
                 model.tertiary_role,
             ],
+            no_shows: model.no_shows,
         }
     }
 
-    pub fn base_priority(&self) -> HashMap<Role, f32> {
-        let mut priorities = HashMap::new();
-        let time = self.last_played.unwrap_or(Utc::now().naive_utc());
-        let time_since = (Utc::now().naive_utc() - time).num_minutes() as f32;
-        let priority_points = 100.0 + (time_since / 5.0).powf(1.5);
+    pub fn priority_points(&self, now: DateTime) -> f32 {
+        let time = self.last_played.unwrap_or(now);
+        let time_since = (now - time).num_minutes().max(0) as f32;
+
+        let penalty = NO_SHOW_PENALTY * self.no_shows as f32;
+
+        (100.0 + (time_since / 5.0).powf(1.5) - penalty).max(1.0)
+    }
+
+    pub fn base_priority(&self, now: DateTime) -> BTreeMap<Role, f32> {
+        let mut priorities = BTreeMap::new();
+        let priority_points = self.priority_points(now);
 
         if self.flex {
             let role_count = Role::iter().count();
@@ -83,4 +97,186 @@ impl Player {
 
         priorities
     }
+
+    pub fn main_role(&self) -> Option<Role> {
+        let preferred = self
+            .priority_roles
+            .iter()
+            .flatten()
+            .find(|role| self.ranks[role].rd < CALIBRATED_RD);
+
+        preferred.copied().or_else(|| {
+            Role::iter()
+                .filter(|role| self.ranks[role].rd < CALIBRATED_RD)
+                .min_by(|a, b| {
+                    self.ranks[a]
+                        .rd
+                        .partial_cmp(&self.ranks[b].rd)
+                        .unwrap_or(Ordering::Equal)
+                })
+        })
+    }
+
+    pub fn rating(&self, role: &Role) -> Rating {
+        let rating = self.ranks[role];
+        if rating.rd < CALIBRATED_RD {
+            return rating;
+        }
+
+        let main_role = match self.main_role() {
+            Some(main_role) if main_role != *role => main_role,
+            _ => return rating,
+        };
+        let main_rating = self.ranks[&main_role];
+
+        let estimate =
+            main_rating.value - OFF_ROLE_PENALTY * self.off_role_distance(&main_role, role);
+
+        // the closer the off-role rating is to being calibrated, the more we trust it
+        let weight =
+            ((rating.rd - CALIBRATED_RD) / (UNCALIBRATED_RD - CALIBRATED_RD)).clamp(0.0, 1.0);
+
+        Rating::new(
+            estimate * weight + rating.value * (1.0 - weight),
+            rating.rd,
+            rating.volatility,
+        )
+    }
+
+    pub fn open_queue_role(&self) -> Role {
+        if !self.flex {
+            if let Some(role) = self.priority_roles.iter().flatten().next() {
+                return *role;
+            }
+        }
+
+        Role::iter()
+            .max_by(|a, b| self.rating(a).cmp(&self.rating(b)))
+            .unwrap()
+    }
+
+    fn off_role_distance(&self, main_role: &Role, role: &Role) -> f32 {
+        if self.flex {
+            return 1.0;
+        }
+
+        let position = |role: &Role| {
+            self.priority_roles
+                .iter()
+                .position(|r| r.as_ref() == Some(role))
+        };
+
+        match (position(main_role), position(role)) {
+            (Some(main), Some(off)) => off.abs_diff(main) as f32,
+            _ => self.priority_roles.len() as f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ratings as (value, rd) for tank, dps and support
+    fn player(roles: [Option<Role>; 3], flex: bool, ranks: [(f32, f32); 3]) -> Player {
+        let [tank, dps, support] = ranks;
+        Player::new(players::Model {
+            id: 1,
+            discord_id: 1,
+            bn_name: None,
+            bn_tag: None,
+            last_played: None,
+            tank_rating: tank.0,
+            tank_rd: tank.1,
+            tank_volatility: 0.06,
+            dps_rating: dps.0,
+            dps_rd: dps.1,
+            dps_volatility: 0.06,
+            support_rating: support.0,
+            support_rd: support.1,
+            support_volatility: 0.06,
+            flex,
+            primary_role: roles[0],
+            secondary_role: roles[1],
+            tertiary_role: roles[2],
+            no_shows: 0,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        })
+    }
+
+    #[test]
+    fn calibrated_role_uses_its_own_rating() {
+        let player = player(
+            [Some(Role::Tank), Some(Role::Dps), None],
+            false,
+            [(2500.0, 100.0), (2000.0, 150.0), (1500.0, 350.0)],
+        );
+
+        assert_eq!(player.rating(&Role::Tank), player.ranks[&Role::Tank]);
+        assert_eq!(player.rating(&Role::Dps), player.ranks[&Role::Dps]);
+    }
+
+    #[test]
+    fn main_role_is_the_first_calibrated_priority_role() {
+        let calibrated = player(
+            [Some(Role::Dps), Some(Role::Tank), None],
+            false,
+            [(2500.0, 100.0), (2000.0, 350.0), (1500.0, 50.0)],
+        );
+        let uncalibrated = player(
+            [Some(Role::Dps), None, None],
+            false,
+            [(2500.0, 350.0), (2000.0, 350.0), (1500.0, 350.0)],
+        );
+
+        assert_eq!(calibrated.main_role(), Some(Role::Tank));
+        assert_eq!(uncalibrated.main_role(), None);
+        assert_eq!(
+            uncalibrated.rating(&Role::Dps),
+            uncalibrated.ranks[&Role::Dps]
+        );
+    }
+
+    #[test]
+    fn uncalibrated_off_role_is_estimated_from_the_main_role() {
+        let player = player(
+            [Some(Role::Tank), Some(Role::Dps), None],
+            false,
+            [(2500.0, 100.0), (1500.0, UNCALIBRATED_RD), (1500.0, 350.0)],
+        );
+
+        // one step down the priority list, and past the end of it for an unlisted role
+        assert_eq!(player.rating(&Role::Dps).value, 2500.0 - OFF_ROLE_PENALTY);
+        assert_eq!(
+            player.rating(&Role::Support).value,
+            2500.0 - 3.0 * OFF_ROLE_PENALTY
+        );
+        assert_eq!(player.rating(&Role::Dps).rd, UNCALIBRATED_RD);
+    }
+
+    #[test]
+    fn off_role_estimate_fades_as_the_role_calibrates() {
+        let halfway = (CALIBRATED_RD + UNCALIBRATED_RD) / 2.0;
+        let player = player(
+            [Some(Role::Tank), Some(Role::Dps), None],
+            false,
+            [(2500.0, 100.0), (1500.0, halfway), (1500.0, 350.0)],
+        );
+
+        let estimate = 2500.0 - OFF_ROLE_PENALTY;
+        assert_eq!(player.rating(&Role::Dps).value, (estimate + 1500.0) / 2.0);
+        assert_eq!(player.rating(&Role::Dps).rd, halfway);
+    }
+
+    #[test]
+    fn open_queue_role_follows_preference_unless_flex() {
+        let ranks = [(2000.0, 100.0), (2600.0, 100.0), (2200.0, 100.0)];
+        let support_main = player([Some(Role::Support), None, None], false, ranks);
+        let flex = player([Some(Role::Support), None, None], true, ranks);
+
+        assert_eq!(support_main.open_queue_role(), Role::Support);
+        assert_eq!(flex.open_queue_role(), Role::Dps);
+    }
 }
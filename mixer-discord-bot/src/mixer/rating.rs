@@ -110,4 +110,13 @@ impl Rating {
         self.rd = rd;
         self.volatility = sigma;
     }
+
+    pub fn update_weighted(&mut self, other: &Self, score: f32, weight: f32) {
+        let before = *self;
+        self.update(other, score);
+
+        self.value = before.value + (self.value - before.value) * weight;
+        self.rd = before.rd + (self.rd - before.rd) * weight;
+        self.volatility = before.volatility + (self.volatility - before.volatility) * weight;
+    }
 }
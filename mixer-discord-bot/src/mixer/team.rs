@@ -1,5 +1,5 @@
 use sea_orm::Iterable;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::mixer::player::Player;
 use crate::mixer::rating::Rating;
@@ -7,17 +7,18 @@ use entity::prelude::Role;
 
 #[derive(Debug, Clone)]
 pub struct Team {
-    pub players: HashMap<(Role, i32), Option<usize>>,
+    pub players: BTreeMap<(Role, i32), Option<usize>>,
 
     max_role: HashMap<Role, usize>,
     count_role: HashMap<Role, usize>,
+    max_size: usize,
 }
 
 impl Team {
     pub fn new(slots: Vec<Role>) -> Self {
         Self {
             players: {
-                let mut players = HashMap::new();
+                let mut players = BTreeMap::new();
                 for role in Role::iter() {
                     for i in 0..slots.iter().filter(|slot| **slot == role).count() {
                         players.insert((role, i as i32), None);
@@ -39,6 +40,16 @@ impl Team {
                 }
                 count_role
             },
+            max_size: slots.len(),
+        }
+    }
+
+    pub fn new_open(size: usize) -> Self {
+        Self {
+            players: BTreeMap::new(),
+            max_role: Role::iter().map(|role| (role, size)).collect(),
+            count_role: Role::iter().map(|role| (role, 0)).collect(),
+            max_size: size,
         }
     }
 
@@ -55,7 +66,7 @@ impl Team {
             .iter()
             .map(|((role, _), index)| {
                 if let Some(index) = index {
-                    *players[*index].ranks.get(role).unwrap()
+                    players[*index].rating(role)
                 } else {
                     Rating::zero()
                 }
@@ -77,7 +88,7 @@ impl Team {
             .filter(|((r, _), _)| r == role)
             .map(|((_, _), index)| {
                 if let Some(index) = index {
-                    *players[*index].ranks.get(role).unwrap()
+                    players[*index].rating(role)
                 } else {
                     Rating::zero()
                 }
@@ -95,7 +106,7 @@ impl Team {
     }
 
     pub fn has_slot(&self, role: &Role) -> bool {
-        self.count_role(role) < *self.max_role.get(role).unwrap()
+        self.count_role(role) < *self.max_role.get(role).unwrap() && self.count() < self.max_size
     }
 
     pub fn add_player(&mut self, index: usize, role: &Role) {
@@ -107,4 +118,12 @@ impl Team {
         self.players.insert((*role, count as i32), Some(index));
         self.count_role.insert(*role, count + 1);
     }
+
+    pub fn remap(&self, indices: &[usize]) -> Self {
+        let mut team = self.clone();
+        for index in team.players.values_mut().flatten() {
+            *index = indices[*index];
+        }
+        team
+    }
 }